@@ -1,72 +1,291 @@
 //! Status bar.
 
 use crate::{
-    block::{Block, Stream},
+    block::{Block, Stream, Style},
+    click::Click,
     config::Config,
     format::Format,
-    modules::Module,
+    modules::{Module, Registry},
+    theme::Theme,
 };
 use futures_util::{
-    stream::{self, SelectAll},
+    stream::{self, LocalBoxStream, SelectAll},
     StreamExt,
 };
+use std::{error::Error, fmt, mem, time::Duration};
+use tokio::sync::mpsc::{self, UnboundedSender};
 
 /// Status bar.
 pub struct Bar {
     format: Format,
+    theme: Theme,
+    /// The style of the blocks of each module.
+    styles: Vec<Style>,
+    initialized: bool,
+    /// The block of each module, followed by a block for showing errors in the status bar itself.
     cache: Vec<Option<Block>>,
+    clicks: LocalBoxStream<'static, Click>,
+    click_senders: Vec<UnboundedSender<Click>>,
     updates: SelectAll<Stream>,
 }
 
 impl Bar {
-    /// Creates a new instance of [`Bar`].
+    /// Creates a new instance of [`Bar`], resolving the colors of blocks with `theme`.
+    ///
+    /// The blocks of the modules are left unstyled, see [`Bar::with_styles`].
     #[must_use]
-    pub fn new(format: Format, modules: Vec<Module>) -> Self {
-        let cache = vec![None; modules.len()];
+    pub fn new(format: Format, theme: Theme, modules: Vec<Box<dyn Module>>) -> Self {
+        let styles = vec![Style::default(); modules.len()];
+        let cache = vec![None; modules.len() + 1];
+        let clicks = format.clicks();
 
-        let updates = modules
+        let (click_senders, updates): (Vec<_>, Vec<_>) = modules
             .into_iter()
             .enumerate()
-            .map(|(position, module)| match module {
-                #[cfg(feature = "clock")]
-                Module::Clock(module) => Stream::new(position, module.stream()),
-                #[cfg(feature = "battery")]
-                Module::Battery(module) => Stream::new(position, module.stream()),
-                #[cfg(feature = "battery")]
-                Module::Bluetooth(module) => Stream::new(position, module.stream()),
-                #[cfg(feature = "battery")]
-                Module::BluetoothDevice(module) => Stream::new(position, module.stream()),
-                #[cfg(feature = "wifi")]
-                Module::Wifi(module) => Stream::new(position, module.stream()),
-            });
+            .map(|(position, module)| {
+                let (sender, receiver) = mpsc::unbounded_channel();
+                (sender, Stream::new(position, module.stream(receiver)))
+            })
+            .unzip();
 
         let updates = stream::select_all(updates);
 
         Self {
             format,
+            theme,
+            styles,
+            initialized: false,
             cache,
+            clicks,
+            click_senders,
             updates,
         }
     }
 
+    /// Creates a new instance of [`Bar`] from a config, building its modules with `registry`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when a module cannot be built from its config.
+    pub fn from_config(config: Config, registry: &Registry) -> Result<Self, toml::de::Error> {
+        let format = config.output_format();
+
+        let styles = config
+            .modules
+            .iter()
+            .map(|module| module.style.clone())
+            .collect();
+
+        let modules = config
+            .modules
+            .into_iter()
+            .map(|module| registry.build(module))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self::new(format, config.theme, modules).with_styles(styles))
+    }
+
+    /// Sets the style of the blocks of each module, in the same order as the modules.
+    #[must_use]
+    pub fn with_styles(mut self, styles: Vec<Style>) -> Self {
+        self.styles = styles;
+        self
+    }
+
+    /// Styles a block from the module at `id`, and resolves its colors with the theme.
+    fn apply(&self, id: usize, block: Block) -> Block {
+        let block = match self.styles.get(id) {
+            Some(style) => style.apply(block),
+            None => block,
+        };
+
+        self.theme.apply(block)
+    }
+
+    /// Replaces the format and modules of the status bar with those from a new config.
+    ///
+    /// The status bar is only reinitialized if the kind of format changed, so this can be used to
+    /// reload the config of a running status bar.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when a module cannot be built from its config, in which case the status bar
+    /// is left unchanged.
+    pub fn reload(&mut self, config: Config, registry: &Registry) -> Result<(), toml::de::Error> {
+        let mut bar = Self::from_config(config, registry)?;
+
+        if mem::discriminant(&self.format) == mem::discriminant(&bar.format) {
+            bar.initialized = self.initialized;
+            mem::swap(&mut bar.clicks, &mut self.clicks);
+        }
+
+        *self = bar;
+
+        Ok(())
+    }
+
+    /// Shows an error in the status bar itself, for example when reloading the config fails.
+    ///
+    /// The error is cleared when the config is reloaded successfully.
+    pub fn show_error(&mut self, text: String) {
+        if let Some(error) = self.cache.last_mut() {
+            *error = Some(self.theme.apply(Block::error(text)));
+        }
+
+        if self.initialized {
+            self.format.update(&self.cache);
+        }
+    }
+
+    fn init(&mut self) {
+        if !self.initialized {
+            self.format.init();
+            self.initialized = true;
+        }
+    }
+
     /// Runs the status bar, producing updates each time there's a change.
+    ///
+    /// This can be cancelled and run again, for example to reload the config in between.
     #[allow(clippy::future_not_send)]
     pub async fn run(&mut self) {
-        self.format.init();
+        self.init();
+
+        loop {
+            tokio::select! {
+                Some((id, block)) = self.updates.next() => {
+                    let block = block.map(|block| self.apply(id, block));
+
+                    if let Some(cached_block) = self.cache.get_mut(id) {
+                        if *cached_block != block {
+                            *cached_block = block;
+                            self.format.update(&self.cache);
+                        }
+                    }
+                }
+
+                Some(click) = self.clicks.next() => {
+                    self.route_click(click);
+                }
+
+                else => break,
+            }
+        }
+    }
+
+    /// Runs the status bar until every module has produced its first block, or until `timeout`
+    /// elapses, then produces a single update.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when any module didn't produce a block in time, or its first block shows
+    /// an error.
+    #[allow(clippy::future_not_send)]
+    pub async fn run_once(&mut self, timeout: Duration) -> Result<(), Incomplete> {
+        self.init();
+
+        let mut pending = vec![true; self.click_senders.len()];
+
+        // Modules that haven't produced a block when the timeout elapses are reported below.
+        let _ = tokio::time::timeout(timeout, async {
+            while pending.contains(&true) {
+                let Some((id, block)) = self.updates.next().await else {
+                    break;
+                };
+
+                let block = block.map(|block| self.apply(id, block));
 
-        while let Some((id, block)) = self.updates.next().await {
-            if let Some(cached_block) = self.cache.get_mut(id) {
-                if *cached_block != block {
+                if let Some(cached_block) = self.cache.get_mut(id) {
                     *cached_block = block;
-                    self.format.update(&self.cache);
+                    pending[id] = false;
                 }
             }
+        })
+        .await;
+
+        self.format.update(&self.cache);
+
+        let modules = pending
+            .into_iter()
+            .enumerate()
+            .filter_map(|(position, pending)| pending.then_some(position))
+            .collect::<Vec<_>>();
+
+        let failed = self.cache[..self.click_senders.len()]
+            .iter()
+            .enumerate()
+            .filter(|(_, block)| block.as_ref().is_some_and(|block| block.error))
+            .map(|(position, _)| position)
+            .collect::<Vec<_>>();
+
+        if modules.is_empty() && failed.is_empty() {
+            Ok(())
+        } else {
+            Err(Incomplete { modules, failed })
+        }
+    }
+
+    /// Sends a click to the module that produced the clicked block.
+    fn route_click(&self, click: Click) {
+        let sender = click
+            .name
+            .as_deref()
+            .and_then(|name| name.parse::<usize>().ok())
+            .and_then(|id| self.click_senders.get(id));
+
+        if let Some(sender) = sender {
+            // The module may have dropped its receiver if it doesn't react to clicks.
+            let _ = sender.send(click);
         }
     }
 }
 
-impl From<Config> for Bar {
-    fn from(config: Config) -> Self {
-        Self::new(config.format.into(), config.modules)
+impl TryFrom<Config> for Bar {
+    type Error = toml::de::Error;
+
+    fn try_from(config: Config) -> Result<Self, Self::Error> {
+        Self::from_config(config, &Registry::default())
+    }
+}
+
+/// An error for modules that didn't produce a block, or produced an error, when running the
+/// status bar once.
+#[derive(Debug)]
+pub struct Incomplete {
+    /// The positions of the modules that didn't produce a block.
+    pub modules: Vec<usize>,
+    /// The positions of the modules whose block shows an error.
+    pub failed: Vec<usize>,
+}
+
+impl fmt::Display for Incomplete {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let positions = |modules: &[usize]| {
+            modules
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        let mut problems = Vec::new();
+
+        if !self.modules.is_empty() {
+            problems.push(format!(
+                "modules at positions {} didn't produce a block",
+                positions(&self.modules)
+            ));
+        }
+
+        if !self.failed.is_empty() {
+            problems.push(format!(
+                "modules at positions {} failed",
+                positions(&self.failed)
+            ));
+        }
+
+        write!(f, "{}", problems.join(", and "))
     }
 }
+
+impl Error for Incomplete {}
@@ -1,27 +1,85 @@
 //! A status bar block.
 
+use serde::{Deserialize, Serialize};
 use std::{
     pin::Pin,
     task::{Context, Poll},
 };
 
 /// A status bar block.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct Block {
     /// The full text to display.
     pub text: String,
     /// The text to display when the bar is shortened.
     pub short_text: Option<String>,
+    /// The instance of the block, which is sent back with clicks on the block so a module can
+    /// tell which of its parts was clicked.
+    pub instance: Option<String>,
     /// The color to display the text with.
+    ///
+    /// This can be the name of a color from the [`Theme`](crate::theme::Theme).
     pub color: Option<String>,
+    /// The color to display the background of the block with.
+    pub background: Option<String>,
+    /// The color to display the border of the block with.
+    pub border: Option<String>,
+    /// The width of the top border in pixels.
+    pub border_top: Option<u32>,
+    /// The width of the bottom border in pixels.
+    pub border_bottom: Option<u32>,
+    /// The width of the left border in pixels.
+    pub border_left: Option<u32>,
+    /// The width of the right border in pixels.
+    pub border_right: Option<u32>,
+    /// The minimum width of the block.
+    pub min_width: Option<MinWidth>,
+    /// The alignment of the text when the block is wider than its text.
+    pub align: Option<Align>,
+    /// Whether the block needs attention.
+    pub urgent: bool,
+    /// Whether to draw a separator after the block.
+    pub separator: Option<bool>,
+    /// The amount of space to leave after the block in pixels.
+    pub separator_block_width: Option<u32>,
+    /// How the text of the block should be parsed.
+    pub markup: Option<Markup>,
+    /// The text to display when hovering over the block, for formats that support it.
+    pub tooltip: Option<String>,
+    /// A percentage represented by the block, for formats that support it.
+    pub percentage: Option<u8>,
+    /// The state of what the block represents, which is styled by the
+    /// [`Theme`](crate::theme::Theme) and formats.
+    pub state: Option<State>,
+    /// Whether the block shows an error from its module instead of what it represents.
+    pub error: bool,
+}
+
+impl Block {
+    /// Creates a block showing an error.
+    #[must_use]
+    pub fn error(text: String) -> Self {
+        Self {
+            text,
+            color: Some("error".into()),
+            error: true,
+            ..Self::default()
+        }
+    }
+
+    /// Returns whether the block needs attention, either because it's urgent or its state is
+    /// critical.
+    #[must_use]
+    pub fn is_urgent(&self) -> bool {
+        self.urgent || self.state == Some(State::Critical)
+    }
 }
 
 impl From<String> for Block {
     fn from(text: String) -> Self {
         Self {
             text,
-            short_text: None,
-            color: None,
+            ..Self::default()
         }
     }
 }
@@ -32,6 +90,128 @@ impl From<&str> for Block {
     }
 }
 
+/// The style of every block of a module, set with keys shared by every module in the config file.
+///
+/// Each field that's set overrides the same field of the module's blocks.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct Style {
+    /// The color to display the background of the block with.
+    ///
+    /// This can be the name of a color from the [`Theme`](crate::theme::Theme).
+    pub background: Option<String>,
+    /// The color to display the border of the block with.
+    ///
+    /// This can be the name of a color from the [`Theme`](crate::theme::Theme).
+    pub border: Option<String>,
+    /// The width of the top border in pixels.
+    pub border_top: Option<u32>,
+    /// The width of the bottom border in pixels.
+    pub border_bottom: Option<u32>,
+    /// The width of the left border in pixels.
+    pub border_left: Option<u32>,
+    /// The width of the right border in pixels.
+    pub border_right: Option<u32>,
+    /// The minimum width of the block.
+    pub min_width: Option<MinWidth>,
+    /// The alignment of the text when the block is wider than its text.
+    pub align: Option<Align>,
+    /// Whether to draw a separator after the block.
+    ///
+    /// This is set with `show_separator`, as some modules already have a `separator` for the text
+    /// between their parts.
+    #[serde(rename = "show_separator")]
+    pub separator: Option<bool>,
+    /// The amount of space to leave after the block in pixels.
+    pub separator_block_width: Option<u32>,
+    /// How the text of the block should be parsed.
+    pub markup: Option<Markup>,
+}
+
+impl Style {
+    /// Returns `block` with the fields that are set in the style replaced.
+    #[must_use]
+    pub fn apply(&self, block: Block) -> Block {
+        Block {
+            background: self.background.clone().or(block.background),
+            border: self.border.clone().or(block.border),
+            border_top: self.border_top.or(block.border_top),
+            border_bottom: self.border_bottom.or(block.border_bottom),
+            border_left: self.border_left.or(block.border_left),
+            border_right: self.border_right.or(block.border_right),
+            min_width: self.min_width.clone().or(block.min_width),
+            align: self.align.or(block.align),
+            separator: self.separator.or(block.separator),
+            separator_block_width: self.separator_block_width.or(block.separator_block_width),
+            markup: self.markup.or(block.markup),
+            ..block
+        }
+    }
+}
+
+/// The minimum width of a block.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MinWidth {
+    /// A width in pixels.
+    Pixels(u32),
+    /// The width of the given text.
+    Text(String),
+}
+
+/// The alignment of a block's text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Align {
+    /// Align the text to the left.
+    Left,
+    /// Center the text.
+    Center,
+    /// Align the text to the right.
+    Right,
+}
+
+/// The markup language of a block's text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Markup {
+    /// Plain text.
+    None,
+    /// Pango markup.
+    Pango,
+}
+
+/// The state of what a block represents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum State {
+    /// Inactive, for example a disconnected device.
+    Idle,
+    /// Worth noting, but not a problem.
+    Info,
+    /// Good, for example a charging battery.
+    Good,
+    /// Needs attention soon.
+    Warning,
+    /// Needs attention now, for example a nearly empty battery.
+    Critical,
+}
+
+impl State {
+    /// Returns the name of the state, which is also the name of its color in the
+    /// [`Theme`](crate::theme::Theme).
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Idle => "idle",
+            Self::Info => "info",
+            Self::Good => "good",
+            Self::Warning => "warning",
+            Self::Critical => "critical",
+        }
+    }
+}
+
 /// A type erased stream of blocks.
 pub struct Stream {
     id: usize,
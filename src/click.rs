@@ -0,0 +1,102 @@
+//! Click events on status bar blocks.
+
+use serde::Deserialize;
+use std::process::Stdio;
+use tokio::{process::Command, sync::mpsc::UnboundedReceiver};
+
+/// A stream of clicks on a module's block.
+pub type Clicks = UnboundedReceiver<Click>;
+
+/// A mouse button.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(from = "u8")]
+pub enum Button {
+    /// The left mouse button.
+    Left,
+    /// The middle mouse button.
+    Middle,
+    /// The right mouse button.
+    Right,
+    /// Scrolling up.
+    ScrollUp,
+    /// Scrolling down.
+    ScrollDown,
+    /// Scrolling left.
+    ScrollLeft,
+    /// Scrolling right.
+    ScrollRight,
+    /// The back mouse button.
+    Back,
+    /// The forward mouse button.
+    Forward,
+    /// Any other mouse button.
+    Other(u8),
+}
+
+impl From<u8> for Button {
+    fn from(button: u8) -> Self {
+        match button {
+            1 => Self::Left,
+            2 => Self::Middle,
+            3 => Self::Right,
+            4 => Self::ScrollUp,
+            5 => Self::ScrollDown,
+            6 => Self::ScrollLeft,
+            7 => Self::ScrollRight,
+            8 => Self::Back,
+            9 => Self::Forward,
+            button => Self::Other(button),
+        }
+    }
+}
+
+/// A click on a status bar block.
+///
+/// This follows the click events of the i3bar protocol.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Click {
+    /// The name of the clicked block.
+    ///
+    /// This is set by the status bar to route the click to the module that produced the block.
+    pub name: Option<String>,
+    /// The instance of the clicked block, as set by the module in
+    /// [`Block::instance`](crate::block::Block::instance).
+    pub instance: Option<String>,
+    /// The mouse button that was clicked.
+    pub button: Button,
+    /// The modifiers that were held down, for example `Shift` or `Mod4`.
+    #[serde(default)]
+    pub modifiers: Vec<String>,
+    /// The x coordinate of the click relative to the top left of the root window.
+    #[serde(default)]
+    pub x: i32,
+    /// The y coordinate of the click relative to the top left of the root window.
+    #[serde(default)]
+    pub y: i32,
+    /// The x coordinate of the click relative to the top left of the block.
+    #[serde(default)]
+    pub relative_x: i32,
+    /// The y coordinate of the click relative to the top left of the block.
+    #[serde(default)]
+    pub relative_y: i32,
+    /// The width of the block in pixels.
+    #[serde(default)]
+    pub width: i32,
+    /// The height of the block in pixels.
+    #[serde(default)]
+    pub height: i32,
+}
+
+/// Runs a shell command in the background.
+///
+/// The command's output is discarded so it can't interfere with the status bar output.
+pub fn spawn_command(command: &str) {
+    // There's nowhere to report a failure to start the command, so it's ignored.
+    let _ = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+}
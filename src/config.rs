@@ -1,10 +1,12 @@
 //! Types for configuring a status bar.
 
-use crate::{format, modules::Module};
+use crate::{block::Style, format, theme::Theme};
 use serde::Deserialize;
+#[cfg(feature = "tmux")]
+use std::path::PathBuf;
 
 /// Output format of the status bar.
-#[derive(Deserialize)]
+#[derive(Clone, Copy, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Format {
     /// Debug output.
@@ -12,23 +14,228 @@ pub enum Format {
     /// Swaybar output.
     #[cfg(feature = "swaybar")]
     Swaybar,
+    /// Waybar output.
+    #[cfg(feature = "waybar")]
+    Waybar,
+    /// Lemonbar output.
+    #[cfg(feature = "lemonbar")]
+    Lemonbar,
+    /// Plain text output.
+    #[cfg(feature = "plain")]
+    Plain,
+    /// Tmux status line output.
+    #[cfg(feature = "tmux")]
+    Tmux,
 }
 
-impl From<Format> for format::Format {
-    fn from(format: Format) -> Self {
-        match format {
-            Format::Debug => Self::Debug,
-            #[cfg(feature = "swaybar")]
-            Format::Swaybar => Self::Swaybar,
+/// Options for the waybar output format.
+#[cfg(feature = "waybar")]
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Waybar {
+    /// The position of the module to print, starting from 0.
+    ///
+    /// When this isn't set, every module is combined into one.
+    pub module: Option<usize>,
+    /// The text to display between blocks when every module is combined into one.
+    pub separator: String,
+}
+
+#[cfg(feature = "waybar")]
+impl Default for Waybar {
+    fn default() -> Self {
+        Self {
+            module: None,
+            separator: " | ".into(),
         }
     }
 }
 
+/// Options for the lemonbar output format.
+#[cfg(feature = "lemonbar")]
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Lemonbar {
+    /// The text to display between blocks.
+    pub separator: String,
+    /// The positions of the modules to align to the left, starting from 0.
+    pub left: Vec<usize>,
+    /// The positions of the modules to align to the center, starting from 0.
+    ///
+    /// Modules that aren't aligned to the left or center are aligned to the right.
+    pub center: Vec<usize>,
+}
+
+#[cfg(feature = "lemonbar")]
+impl Default for Lemonbar {
+    fn default() -> Self {
+        Self {
+            separator: " | ".into(),
+            left: Vec::new(),
+            center: Vec::new(),
+        }
+    }
+}
+
+/// Options for the plain text output format.
+#[cfg(feature = "plain")]
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Plain {
+    /// The text to display between blocks.
+    pub separator: String,
+}
+
+#[cfg(feature = "plain")]
+impl Default for Plain {
+    fn default() -> Self {
+        Self {
+            separator: " | ".into(),
+        }
+    }
+}
+
+/// Options for the tmux status line output format.
+#[cfg(feature = "tmux")]
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Tmux {
+    /// The text to display between blocks.
+    pub separator: String,
+    /// The file or FIFO to write the status line to.
+    ///
+    /// When this isn't set, the status line is printed.
+    pub path: Option<PathBuf>,
+}
+
+#[cfg(feature = "tmux")]
+impl Default for Tmux {
+    fn default() -> Self {
+        Self {
+            separator: " | ".into(),
+            path: None,
+        }
+    }
+}
+
+/// Configuration for a status bar module.
+#[derive(Deserialize)]
+pub struct Module {
+    /// The kind of module, as registered in a [`Registry`](crate::modules::Registry).
+    pub kind: String,
+    /// The style of the module's blocks, set with keys shared by every module.
+    #[serde(flatten)]
+    pub style: Style,
+    /// The module specific configuration.
+    #[serde(flatten)]
+    pub config: toml::value::Table,
+}
+
 /// Configuration for a status bar.
 #[derive(Deserialize)]
 pub struct Config {
     /// Output format of the status bar.
     pub format: Format,
+    /// Options for the waybar output format.
+    #[cfg(feature = "waybar")]
+    #[serde(default)]
+    pub waybar: Waybar,
+    /// Options for the lemonbar output format.
+    #[cfg(feature = "lemonbar")]
+    #[serde(default)]
+    pub lemonbar: Lemonbar,
+    /// Options for the plain text output format.
+    #[cfg(feature = "plain")]
+    #[serde(default)]
+    pub plain: Plain,
+    /// Options for the tmux status line output format.
+    #[cfg(feature = "tmux")]
+    #[serde(default)]
+    pub tmux: Tmux,
+    /// Named colors used by the modules.
+    #[serde(default)]
+    pub theme: Theme,
     /// Configuration for each status bar module.
     pub modules: Vec<Module>,
 }
+
+impl Config {
+    /// Returns the output format of the status bar, configured with its options.
+    // Only the formats with options clone them, so this could be const without them.
+    #[cfg_attr(
+        not(any(
+            feature = "waybar",
+            feature = "lemonbar",
+            feature = "plain",
+            feature = "tmux"
+        )),
+        allow(clippy::missing_const_for_fn)
+    )]
+    #[must_use]
+    pub fn output_format(&self) -> format::Format {
+        match self.format {
+            Format::Debug => format::Format::Debug,
+            #[cfg(feature = "swaybar")]
+            Format::Swaybar => format::Format::Swaybar,
+            #[cfg(feature = "waybar")]
+            Format::Waybar => format::Format::Waybar(format::waybar::Waybar::new(
+                self.waybar.module,
+                self.waybar.separator.clone(),
+            )),
+            #[cfg(feature = "lemonbar")]
+            Format::Lemonbar => format::Format::Lemonbar(format::lemonbar::Lemonbar::new(
+                self.lemonbar.separator.clone(),
+                self.lemonbar.left.clone(),
+                self.lemonbar.center.clone(),
+            )),
+            #[cfg(feature = "plain")]
+            Format::Plain => {
+                format::Format::Plain(format::plain::Plain::new(self.plain.separator.clone()))
+            }
+            #[cfg(feature = "tmux")]
+            Format::Tmux => format::Format::Tmux(format::tmux::Tmux::new(
+                self.tmux.separator.clone(),
+                self.tmux.path.clone(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::{Align, Block};
+
+    #[test]
+    fn style_keys_are_taken_from_module_config() {
+        let module = toml::from_str::<Module>(
+            r##"
+            kind = "disk"
+            separator = " / "
+            background = "#112233"
+            border_top = 2
+            align = "center"
+            show_separator = false
+            "##,
+        )
+        .expect("valid module");
+
+        assert_eq!(module.kind, "disk");
+        assert_eq!(module.config.keys().collect::<Vec<_>>(), ["separator"]);
+
+        let block = module.style.apply(Block {
+            text: "text".into(),
+            border: Some("#445566".into()),
+            border_top: Some(1),
+            ..Block::default()
+        });
+
+        assert_eq!(block.text, "text");
+        assert_eq!(block.background.as_deref(), Some("#112233"));
+        assert_eq!(block.border.as_deref(), Some("#445566"));
+        assert_eq!(block.border_top, Some(2));
+        assert_eq!(block.align, Some(Align::Center));
+        assert_eq!(block.separator, Some(false));
+        assert_eq!(block.markup, None);
+    }
+}
@@ -1,13 +1,191 @@
 //! Utilities for working with dbus.
 
-use futures_util::{future::OptionFuture, stream::Next, StreamExt};
-use std::cmp::PartialEq;
+use futures_util::{
+    future::{self, OptionFuture},
+    stream::{BoxStream, Next},
+    StreamExt,
+};
+use std::{cmp::PartialEq, fmt};
 use zbus::{
-    fdo::ManagedObjects,
+    fdo::{DBusProxy, ManagedObjects},
     zvariant::{ObjectPath, Value},
-    PropertyStream,
+    Connection, PropertyStream,
+};
+use zbus::{
+    fdo::{InterfacesAddedStream, ObjectManagerProxy},
+    zvariant::OwnedObjectPath,
 };
-use zbus::{fdo::ObjectManagerProxy, zvariant::OwnedObjectPath};
+
+/// An error from a module watching a dbus service.
+#[derive(Debug)]
+pub enum Error {
+    /// Communication with dbus failed.
+    Zbus(zbus::Error),
+    /// The object to watch doesn't exist.
+    NotFound(String),
+    /// The service stopped.
+    Stopped,
+    /// The service restarted.
+    Restarted,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Zbus(error) => error.fmt(f),
+            Self::NotFound(object) => write!(f, "{object} not found"),
+            Self::Stopped => write!(f, "service stopped"),
+            Self::Restarted => write!(f, "service restarted"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<zbus::Error> for Error {
+    fn from(error: zbus::Error) -> Self {
+        Self::Zbus(error)
+    }
+}
+
+impl From<zbus::fdo::Error> for Error {
+    fn from(error: zbus::fdo::Error) -> Self {
+        Self::Zbus(error.into())
+    }
+}
+
+/// A stream of changes to the owner of a service.
+///
+/// Each item is whether the service has an owner after the change.
+pub type OwnerChanges = BoxStream<'static, bool>;
+
+/// Returns a stream of changes to the owner of a service.
+///
+/// The owner changes when the service starts, stops or restarts.
+///
+/// # Errors
+///
+/// Returns an error when communication with dbus fails.
+pub async fn owner_changes(
+    connection: &Connection,
+    service: &'static str,
+) -> zbus::Result<OwnerChanges> {
+    let changes = DBusProxy::new(connection)
+        .await?
+        .receive_name_owner_changed()
+        .await?;
+
+    Ok(changes
+        .filter_map(move |signal| {
+            future::ready(
+                signal
+                    .args()
+                    .ok()
+                    .filter(|args| args.name().as_str() == service)
+                    .map(|args| args.new_owner().is_some()),
+            )
+        })
+        .boxed())
+}
+
+/// Returns whether a service has an owner, meaning that it's running.
+///
+/// # Errors
+///
+/// Returns an error when communication with dbus fails.
+pub async fn has_owner(connection: &Connection, service: &'static str) -> zbus::Result<bool> {
+    DBusProxy::new(connection)
+        .await?
+        .name_has_owner(service.try_into()?)
+        .await
+        .map_err(Into::into)
+}
+
+/// Waits until a service has an owner.
+///
+/// Returns `false` if the owner changes can no longer be received.
+pub async fn wait_for_owner(owner_changes: &mut OwnerChanges) -> bool {
+    while let Some(has_owner) = owner_changes.next().await {
+        if has_owner {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// A stream of objects added to a service.
+pub type ObjectsAdded = InterfacesAddedStream<'static>;
+
+/// Returns a stream of objects added to a service, as reported by its object manager at `path`.
+///
+/// This should only be kept while waiting for an object, as signals that aren't read hold up
+/// every other message on the connection.
+///
+/// # Errors
+///
+/// Returns an error when communication with dbus fails.
+pub async fn objects_added(
+    connection: &Connection,
+    service: &'static str,
+    path: &'static str,
+) -> zbus::Result<ObjectsAdded> {
+    ObjectManagerProxy::builder(connection)
+        .destination(service)?
+        .path(path)?
+        .build()
+        .await?
+        .receive_interfaces_added()
+        .await
+}
+
+/// Waits until an object is added to a service, or the service starts or restarts, so a module
+/// can look for an object that didn't exist yet.
+///
+/// When the objects added can't be received, this only waits for the service to start or restart.
+/// Returns `false` if the owner changes can no longer be received.
+pub async fn wait_for_object(
+    objects_added: Option<&mut ObjectsAdded>,
+    owner_changes: &mut OwnerChanges,
+) -> bool {
+    let next_object = OptionFuture::from(objects_added.map(StreamExt::next));
+
+    tokio::select! {
+        Some(Some(_)) = next_object => true,
+
+        has_owner = owner_changes.next() => match has_owner {
+            Some(true) => true,
+            Some(false) => wait_for_owner(owner_changes).await,
+            None => false,
+        },
+    }
+}
+
+/// Converts a change of a service's owner into an error, for modules that need to reconnect.
+#[must_use]
+pub const fn owner_change_error(has_owner: bool) -> Error {
+    if has_owner {
+        Error::Restarted
+    } else {
+        Error::Stopped
+    }
+}
+
+/// Creates a proxy for the object manager at the root of a service.
+///
+/// # Errors
+///
+/// Returns an error when communication with dbus fails.
+pub async fn object_manager<'a>(
+    connection: &Connection,
+    service: &'a str,
+) -> zbus::Result<ObjectManagerProxy<'a>> {
+    ObjectManagerProxy::builder(connection)
+        .destination(service)?
+        .path("/")?
+        .build()
+        .await
+}
 
 /// Finds the path of an interface with a given property value.
 ///
@@ -53,6 +231,7 @@ where
     for<'v> &'v V: TryFrom<&'v Value<'v>>,
 {
     for (path, interfaces) in objects {
+        #[allow(clippy::manual_is_variant_and)]
         let is_match = interfaces
             .get(interface_name)
             .and_then(|properties| properties.get(property_name))
@@ -1,6 +1,21 @@
 //! Status bar output format.
 
-use crate::block::Block;
+#[cfg(feature = "lemonbar")]
+pub mod lemonbar;
+#[cfg(feature = "plain")]
+pub mod plain;
+#[cfg(feature = "swaybar")]
+pub mod swaybar;
+#[cfg(feature = "tmux")]
+pub mod tmux;
+#[cfg(feature = "waybar")]
+pub mod waybar;
+
+use crate::{block::Block, click::Click};
+use futures_util::{
+    stream::{self, LocalBoxStream},
+    StreamExt,
+};
 
 /// Status bar output format.
 pub enum Format {
@@ -9,18 +24,41 @@ pub enum Format {
     /// Swaybar output format.
     #[cfg(feature = "swaybar")]
     Swaybar,
+    /// Waybar output format.
+    #[cfg(feature = "waybar")]
+    Waybar(self::waybar::Waybar),
+    /// Lemonbar output format.
+    #[cfg(feature = "lemonbar")]
+    Lemonbar(self::lemonbar::Lemonbar),
+    /// Plain text output format.
+    #[cfg(feature = "plain")]
+    Plain(self::plain::Plain),
+    /// Tmux status line output format.
+    #[cfg(feature = "tmux")]
+    Tmux(self::tmux::Tmux),
 }
 
 impl Format {
     /// Initializes the status bar.
+    // Only swaybar needs initializing, so this could be const without it.
+    #[cfg_attr(not(feature = "swaybar"), allow(clippy::missing_const_for_fn))]
     pub fn init(&mut self) {
         match self {
-            Self::Debug => {}
             #[cfg(feature = "swaybar")]
-            Self::Swaybar => {
-                println!(r#"{{"version":1}}"#);
-                println!("[");
-            }
+            Self::Swaybar => self::swaybar::init(),
+            _ => {}
+        }
+    }
+
+    /// Returns a stream of clicks on the status bar's blocks.
+    ///
+    /// The name of each click is the position of the clicked block.
+    #[must_use]
+    pub fn clicks(&self) -> LocalBoxStream<'static, Click> {
+        match self {
+            #[cfg(feature = "swaybar")]
+            Self::Swaybar => self::swaybar::clicks().boxed_local(),
+            _ => stream::empty().boxed_local(),
         }
     }
 
@@ -32,25 +70,19 @@ impl Format {
             }
 
             #[cfg(feature = "swaybar")]
-            Self::Swaybar => {
-                let bar = blocks
-                    .iter()
-                    .filter_map(|block| {
-                        let block = block.as_ref()?;
-
-                        Some(serde_json::json!({
-                            "full_text": block.text,
-                            "short_text": block.short_text,
-                            "color": block.color,
-                        }))
-                    })
-                    .collect::<Vec<_>>();
-
-                println!(
-                    "{},",
-                    serde_json::to_string(&bar).expect("infallible serialization")
-                );
-            }
+            Self::Swaybar => self::swaybar::update(blocks),
+
+            #[cfg(feature = "waybar")]
+            Self::Waybar(waybar) => waybar.update(blocks),
+
+            #[cfg(feature = "lemonbar")]
+            Self::Lemonbar(lemonbar) => lemonbar.update(blocks),
+
+            #[cfg(feature = "plain")]
+            Self::Plain(plain) => plain.update(blocks),
+
+            #[cfg(feature = "tmux")]
+            Self::Tmux(tmux) => tmux.update(blocks),
         }
     }
 }
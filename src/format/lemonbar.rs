@@ -0,0 +1,79 @@
+//! Lemonbar output format.
+
+use crate::block::Block;
+
+/// Lemonbar output format.
+pub struct Lemonbar {
+    separator: String,
+    left: Vec<usize>,
+    center: Vec<usize>,
+}
+
+impl Lemonbar {
+    /// Creates a new instance of [`Lemonbar`].
+    ///
+    /// The blocks at the positions in `left` and `center` are aligned to the left and center of
+    /// the bar respectively, and every other block is aligned to the right.
+    #[must_use]
+    pub const fn new(separator: String, left: Vec<usize>, center: Vec<usize>) -> Self {
+        Self {
+            separator,
+            left,
+            center,
+        }
+    }
+
+    /// Updates the status bar.
+    pub fn update(&self, blocks: &[Option<Block>]) {
+        let mut left = Vec::new();
+        let mut center = Vec::new();
+        let mut right = Vec::new();
+
+        for (position, block) in blocks.iter().enumerate() {
+            let Some(block) = block else {
+                continue;
+            };
+
+            let section = if self.left.contains(&position) {
+                &mut left
+            } else if self.center.contains(&position) {
+                &mut center
+            } else {
+                &mut right
+            };
+
+            section.push(render(block));
+        }
+
+        println!(
+            "%{{l}}{}%{{c}}{}%{{r}}{}",
+            left.join(&self.separator),
+            center.join(&self.separator),
+            right.join(&self.separator),
+        );
+    }
+}
+
+/// Renders a block with lemonbar formatting tags.
+fn render(block: &Block) -> String {
+    let mut text = block.text.replace('%', "%%");
+
+    if let Some(color) = &block.color {
+        text = format!("%{{F{}}}{text}%{{F-}}", convert_color(color));
+    }
+
+    if let Some(background) = &block.background {
+        text = format!("%{{B{}}}{text}%{{B-}}", convert_color(background));
+    }
+
+    text
+}
+
+/// Converts a color from the `#rrggbbaa` form used by blocks to the `#aarrggbb` form used by
+/// lemonbar.
+fn convert_color(color: &str) -> String {
+    match color.strip_prefix('#') {
+        Some(hex) if hex.len() == 8 => format!("#{}{}", &hex[6..], &hex[..6]),
+        _ => color.to_owned(),
+    }
+}
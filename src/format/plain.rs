@@ -0,0 +1,30 @@
+//! Plain text output format.
+
+use crate::block::Block;
+
+/// Plain text output format.
+///
+/// This prints the text of each block on a single line, which is useful for shell prompts and
+/// other places that can't handle formatting.
+pub struct Plain {
+    separator: String,
+}
+
+impl Plain {
+    /// Creates a new instance of [`Plain`].
+    #[must_use]
+    pub const fn new(separator: String) -> Self {
+        Self { separator }
+    }
+
+    /// Updates the status bar.
+    pub fn update(&self, blocks: &[Option<Block>]) {
+        let text = blocks
+            .iter()
+            .flatten()
+            .map(|block| block.text.as_str())
+            .collect::<Vec<_>>();
+
+        println!("{}", text.join(&self.separator));
+    }
+}
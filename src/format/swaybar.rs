@@ -0,0 +1,116 @@
+//! Swaybar output format.
+//!
+//! See `swaybar-protocol(7)` for details of the protocol.
+
+use crate::{
+    block::{self, Align, Markup, MinWidth},
+    click::Click,
+};
+use async_stream::stream;
+use futures_util::Stream;
+use serde::Serialize;
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+/// Initializes the status bar by printing the protocol header.
+pub fn init() {
+    println!(r#"{{"version":1,"click_events":true}}"#);
+    println!("[");
+}
+
+/// Updates the status bar.
+///
+/// # Panics
+///
+/// Panics if the blocks cannot be serialized, which should never happen.
+pub fn update(blocks: &[Option<block::Block>]) {
+    let bar = blocks
+        .iter()
+        .enumerate()
+        .filter_map(|(position, block)| Some(Block::new(position, block.as_ref()?)))
+        .collect::<Vec<_>>();
+
+    println!(
+        "{},",
+        serde_json::to_string(&bar).expect("infallible serialization")
+    );
+}
+
+/// A block in the swaybar protocol.
+///
+/// Unset fields are omitted so that swaybar uses its own defaults.
+#[derive(Serialize)]
+struct Block<'a> {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    instance: Option<&'a str>,
+    full_text: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    short_text: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    color: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    background: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    border: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    border_top: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    border_bottom: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    border_left: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    border_right: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_width: Option<&'a MinWidth>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    align: Option<Align>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    urgent: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    separator: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    separator_block_width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    markup: Option<Markup>,
+}
+
+impl<'a> Block<'a> {
+    fn new(position: usize, block: &'a block::Block) -> Self {
+        Self {
+            name: position.to_string(),
+            instance: block.instance.as_deref(),
+            full_text: &block.text,
+            short_text: block.short_text.as_deref(),
+            color: block.color.as_deref(),
+            background: block.background.as_deref(),
+            border: block.border.as_deref(),
+            border_top: block.border_top,
+            border_bottom: block.border_bottom,
+            border_left: block.border_left,
+            border_right: block.border_right,
+            min_width: block.min_width.as_ref(),
+            align: block.align,
+            urgent: block.is_urgent(),
+            separator: block.separator,
+            separator_block_width: block.separator_block_width,
+            markup: block.markup,
+        }
+    }
+}
+
+/// Reads swaybar click events from stdin.
+///
+/// The events are sent as an infinite JSON array, with one event per line.
+pub fn clicks() -> impl Stream<Item = Click> {
+    stream! {
+        let mut lines = BufReader::new(tokio::io::stdin()).lines();
+
+        while let Ok(Some(line)) = lines.next_line().await {
+            let event = line.trim().trim_start_matches(['[', ',']);
+
+            if let Ok(click) = serde_json::from_str(event) {
+                yield click;
+            }
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! Tmux status line output format.
+
+use crate::block::Block;
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    os::unix::fs::{FileTypeExt, OpenOptionsExt},
+    path::{Path, PathBuf},
+};
+
+/// Tmux status line output format.
+///
+/// Tmux polls commands in its status line, so the status line can be written to a file or FIFO
+/// that's read with `#(cat <path>)`, instead of being printed.
+pub struct Tmux {
+    separator: String,
+    path: Option<PathBuf>,
+}
+
+impl Tmux {
+    /// Creates a new instance of [`Tmux`].
+    ///
+    /// When `path` is set the status line is written to it, otherwise it's printed.
+    #[must_use]
+    pub const fn new(separator: String, path: Option<PathBuf>) -> Self {
+        Self { separator, path }
+    }
+
+    /// Updates the status bar.
+    pub fn update(&self, blocks: &[Option<Block>]) {
+        let line = blocks
+            .iter()
+            .flatten()
+            .map(render)
+            .collect::<Vec<_>>()
+            .join(&self.separator);
+
+        match &self.path {
+            // There's nowhere to report a failed write, and the next update will try again.
+            Some(path) => drop(write(path, &line)),
+            None => println!("{line}"),
+        }
+    }
+}
+
+/// Renders a block with tmux style tags.
+fn render(block: &Block) -> String {
+    let mut text = block.text.replace('#', "##");
+
+    if let Some(color) = &block.color {
+        text = format!("#[fg={}]{text}#[fg=default]", convert_color(color));
+    }
+
+    if let Some(background) = &block.background {
+        text = format!("#[bg={}]{text}#[bg=default]", convert_color(background));
+    }
+
+    text
+}
+
+/// Converts a color from the `#rrggbbaa` form used by blocks to the `#rrggbb` form used by tmux,
+/// which doesn't support transparency.
+fn convert_color(color: &str) -> &str {
+    if color.starts_with('#') && color.len() == 9 {
+        &color[..7]
+    } else {
+        color
+    }
+}
+
+/// Writes a line to a file or FIFO.
+fn write(path: &Path, line: &str) -> io::Result<()> {
+    if fs::metadata(path).is_ok_and(|metadata| metadata.file_type().is_fifo()) {
+        // Opening a FIFO without a reader would block the status bar, so the line is dropped
+        // instead when nothing is reading.
+        OpenOptions::new()
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(path)?
+            .write_all(format!("{line}\n").as_bytes())
+    } else {
+        // The line is written to a temporary file first so that it's never read half written.
+        let temporary_path = path.with_extension("tmp");
+        fs::write(&temporary_path, format!("{line}\n"))?;
+        fs::rename(temporary_path, path)
+    }
+}
@@ -0,0 +1,121 @@
+//! Waybar output format.
+//!
+//! This produces the JSON output of a waybar `custom` module with `"return-type": "json"`.
+
+use crate::block::{Block, State};
+use serde::Serialize;
+
+/// Waybar output format.
+pub struct Waybar {
+    module: Option<usize>,
+    separator: String,
+    /// The last line printed, so unchanged updates aren't printed again.
+    emitted: Option<String>,
+}
+
+impl Waybar {
+    /// Creates a new instance of [`Waybar`].
+    ///
+    /// When `module` is set, only the block at that position is printed, otherwise every block is
+    /// combined into one, with their texts separated by `separator`.
+    #[must_use]
+    pub const fn new(module: Option<usize>, separator: String) -> Self {
+        Self {
+            module,
+            separator,
+            emitted: None,
+        }
+    }
+
+    /// Updates the status bar, printing a line when the output changed since the last update.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the output cannot be serialized, which should never happen.
+    pub fn update(&mut self, blocks: &[Option<Block>]) {
+        // Waybar hides custom modules with empty text, which is used for hidden blocks.
+        let output = match self.module {
+            Some(module) => blocks
+                .get(module)
+                .and_then(Option::as_ref)
+                .map(Output::new)
+                .unwrap_or_default(),
+            None => Output::combine(blocks.iter().flatten(), &self.separator),
+        };
+
+        let line = serde_json::to_string(&output).expect("infallible serialization");
+
+        if self.emitted.as_ref() != Some(&line) {
+            println!("{line}");
+            self.emitted = Some(line);
+        }
+    }
+}
+
+/// The output of a waybar custom module.
+#[derive(Default, Serialize)]
+struct Output<'a> {
+    text: String,
+    /// The state of the block, which waybar uses to pick an icon from `format-icons`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    alt: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tooltip: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    class: Vec<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    percentage: Option<u8>,
+}
+
+impl<'a> Output<'a> {
+    fn new(block: &'a Block) -> Self {
+        Self {
+            text: block.text.clone(),
+            alt: block.state.map(State::name),
+            tooltip: block.tooltip.clone(),
+            class: classes(block),
+            percentage: block.percentage,
+        }
+    }
+
+    /// Combines several blocks into one, with the classes of every block.
+    fn combine(blocks: impl Iterator<Item = &'a Block>, separator: &str) -> Self {
+        let mut texts = Vec::new();
+        let mut tooltips = Vec::new();
+        let mut class = Vec::new();
+
+        for block in blocks {
+            texts.push(block.text.as_str());
+            tooltips.extend(block.tooltip.as_deref());
+
+            for name in classes(block) {
+                if !class.contains(&name) {
+                    class.push(name);
+                }
+            }
+        }
+
+        Self {
+            text: texts.join(separator),
+            alt: None,
+            tooltip: (!tooltips.is_empty()).then(|| tooltips.join("\n")),
+            class,
+            percentage: None,
+        }
+    }
+}
+
+/// Returns the CSS classes of a block, which are its state and whether it's urgent.
+fn classes(block: &Block) -> Vec<&'static str> {
+    let mut class = Vec::new();
+
+    if let Some(state) = block.state {
+        class.push(state.name());
+    }
+
+    if block.is_urgent() {
+        class.push("urgent");
+    }
+
+    class
+}
@@ -12,7 +12,21 @@
 
 pub mod bar;
 pub mod block;
+pub mod click;
 pub mod config;
 pub mod dbus;
 pub mod format;
 pub mod modules;
+#[cfg(any(feature = "network", feature = "wifi"))]
+pub mod net;
+pub mod template;
+pub mod theme;
+pub mod thresholds;
+#[cfg(any(
+    feature = "bandwidth",
+    feature = "disk",
+    feature = "load",
+    feature = "memory",
+    feature = "temperature"
+))]
+pub mod units;
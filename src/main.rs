@@ -10,24 +10,189 @@
 )]
 #![allow(clippy::multiple_crate_versions)]
 
-use std::error::Error;
-use sustas::{bar::Bar, config::Config};
+use anyhow::{anyhow, bail, Context};
+use serde::{de::value::StrDeserializer, Deserialize};
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use sustas::{
+    bar::Bar,
+    config::{Config, Format},
+    modules::Registry,
+};
+use tokio::signal::unix::{signal, SignalKind};
 
-#[tokio::main(flavor = "current_thread")]
-async fn main() -> Result<(), Box<dyn Error>> {
+/// How long to wait for modules to produce a block when running once.
+const ONCE_TIMEOUT: Duration = Duration::from_secs(5);
+
+const USAGE: &str = "\
+Usage: sustas [OPTIONS]
+
+Options:
+  -c, --config <PATH>    Path of the config file [default: $XDG_CONFIG_HOME/sustas/config.toml]
+  -f, --format <FORMAT>  Output format, overriding the format in the config file
+      --check            Check that the config file is valid and exit
+      --once             Print the status bar once and exit
+      --list-modules     List the available module kinds and exit
+  -h, --help             Print this help and exit
+  -V, --version          Print the version and exit";
+
+/// What to do when run.
+#[derive(Default)]
+enum Action {
+    /// Run the status bar.
+    #[default]
+    Run,
+    /// Check that the config file is valid.
+    Check,
+    /// List the available module kinds.
+    ListModules,
+    /// Print the help.
+    Help,
+    /// Print the version.
+    Version,
+}
+
+/// Command line arguments.
+#[derive(Default)]
+struct Args {
+    action: Action,
+    config: Option<PathBuf>,
+    format: Option<Format>,
+    once: bool,
+}
+
+impl Args {
+    fn parse() -> anyhow::Result<Self> {
+        let mut args = Self::default();
+        let mut iter = std::env::args().skip(1);
+
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "-c" | "--config" => {
+                    let path = iter.next().context("`--config` requires a path")?;
+                    args.config = Some(path.into());
+                }
+                "-f" | "--format" => {
+                    let format = iter.next().context("`--format` requires a format")?;
+                    args.format = Some(
+                        Format::deserialize(StrDeserializer::<serde::de::value::Error>::new(
+                            &format,
+                        ))
+                        .map_err(|error| anyhow!("invalid format: {error}"))?,
+                    );
+                }
+                "--check" => args.action = Action::Check,
+                "--once" => args.once = true,
+                "--list-modules" => args.action = Action::ListModules,
+                "-h" | "--help" => args.action = Action::Help,
+                "-V" | "--version" => args.action = Action::Version,
+                _ => bail!("unknown argument `{arg}`\n\n{USAGE}"),
+            }
+        }
+
+        Ok(args)
+    }
+}
+
+/// Returns the path of the default config file, creating its directory if needed.
+fn default_config_path() -> anyhow::Result<PathBuf> {
     let config_dir = dirs::config_dir()
-        .ok_or("cannot find config directory")?
+        .context("cannot find config directory")?
         .join("sustas");
 
-    std::fs::create_dir_all(&config_dir)?;
+    std::fs::create_dir_all(&config_dir)
+        .with_context(|| format!("cannot create `{}`", config_dir.display()))?;
+
+    Ok(config_dir.join("config.toml"))
+}
+
+/// Reads the config file, overriding its format if `format` is set.
+fn load_config(path: &Path, format: Option<Format>) -> anyhow::Result<Config> {
+    let config = std::fs::read_to_string(path)
+        .with_context(|| format!("cannot read `{}`", path.display()))?;
+
+    let mut config = toml::from_str::<Config>(&config)
+        .with_context(|| format!("invalid config in `{}`", path.display()))?;
+
+    if let Some(format) = format {
+        config.format = format;
+    }
+
+    Ok(config)
+}
+
+/// Reloads the config of a running status bar, showing any error in the status bar.
+fn reload(bar: &mut Bar, registry: &Registry, path: &Path, format: Option<Format>) {
+    let result = load_config(path, format).and_then(|config| {
+        bar.reload(config, registry)
+            .with_context(|| format!("invalid config in `{}`", path.display()))
+    });
+
+    if let Err(error) = result {
+        bar.show_error(format!("{error:#}"));
+    }
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse()?;
+
+    let registry = Registry::default();
+
+    match args.action {
+        Action::Run | Action::Check => {}
+        Action::ListModules => {
+            let mut kinds = registry.kinds().collect::<Vec<_>>();
+            kinds.sort_unstable();
+
+            for kind in kinds {
+                println!("{kind}");
+            }
+
+            return Ok(());
+        }
+        Action::Help => {
+            println!("{USAGE}");
+            return Ok(());
+        }
+        Action::Version => {
+            println!("sustas {}", env!("CARGO_PKG_VERSION"));
+            return Ok(());
+        }
+    }
+
+    let config_path = match args.config {
+        Some(path) => path,
+        None => default_config_path()?,
+    };
+
+    let config = load_config(&config_path, args.format)?;
+
+    let mut bar = Bar::from_config(config, &registry)
+        .with_context(|| format!("invalid config in `{}`", config_path.display()))?;
+
+    if matches!(args.action, Action::Check) {
+        println!("`{}` is valid", config_path.display());
+        return Ok(());
+    }
 
-    let config_path = config_dir.join("config.toml");
+    if args.once {
+        bar.run_once(ONCE_TIMEOUT).await?;
+        return Ok(());
+    }
 
-    let config = std::fs::read_to_string(config_path)?;
-    let config = toml::from_str::<Config>(&config)?;
+    let mut hangups = signal(SignalKind::hangup()).context("cannot handle SIGHUP")?;
 
-    let mut bar = Bar::from(config);
-    bar.run().await;
+    loop {
+        tokio::select! {
+            () = bar.run() => break,
+            Some(()) = hangups.recv() => {
+                reload(&mut bar, &registry, &config_path, args.format);
+            }
+        }
+    }
 
     Ok(())
 }
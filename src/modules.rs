@@ -1,33 +1,125 @@
 //! A collection of status bar modules.
 
+#[cfg(feature = "bandwidth")]
+pub mod bandwidth;
 #[cfg(feature = "battery")]
 pub mod battery;
 #[cfg(feature = "bluetooth")]
 pub mod bluetooth;
 #[cfg(feature = "clock")]
 pub mod clock;
+#[cfg(feature = "cpu")]
+pub mod cpu;
+#[cfg(feature = "disk")]
+pub mod disk;
+#[cfg(feature = "load")]
+pub mod load;
+#[cfg(feature = "memory")]
+pub mod memory;
+#[cfg(feature = "network")]
+pub mod network;
+#[cfg(feature = "temperature")]
+pub mod temperature;
 #[cfg(feature = "wifi")]
 pub mod wifi;
 
-use serde::Deserialize;
+use crate::{block::Block, click::Clicks, config};
+use futures_util::stream::LocalBoxStream;
+use serde::de::{DeserializeOwned, Error as _};
+use std::collections::HashMap;
 
 /// A status bar module.
-#[derive(Deserialize)]
-#[serde(rename_all = "snake_case", tag = "kind")]
-pub enum Module {
-    /// Clock module.
-    #[cfg(feature = "clock")]
-    Clock(self::clock::Clock),
-    /// Battery module.
-    #[cfg(feature = "battery")]
-    Battery(self::battery::Battery),
-    /// Bluetooth module.
-    #[cfg(feature = "bluetooth")]
-    Bluetooth(self::bluetooth::Bluetooth),
-    /// Bluetooth device module.
-    #[cfg(feature = "bluetooth")]
-    BluetoothDevice(self::bluetooth::BluetoothDevice),
-    /// Wi-Fi module.
-    #[cfg(feature = "wifi")]
-    Wifi(self::wifi::Config),
+///
+/// Modules are built from their section of the config file by a [`Registry`], and then turned
+/// into a stream of block updates by the status bar.
+pub trait Module {
+    /// Returns a stream of block updates.
+    ///
+    /// Yielding `None` hides the module's block. Clicks on the module's block are sent to
+    /// `clicks`, which can be dropped by modules that don't react to clicks.
+    fn stream(self: Box<Self>, clicks: Clicks) -> LocalBoxStream<'static, Option<Block>>;
+}
+
+type Constructor = fn(toml::Value) -> Result<Box<dyn Module>, toml::de::Error>;
+
+/// A registry of module kinds.
+///
+/// Each module kind is identified by the `kind` key of its section in the config file.
+pub struct Registry {
+    constructors: HashMap<String, Constructor>,
+}
+
+impl Registry {
+    /// Creates an empty registry.
+    ///
+    /// Use [`Registry::default`] to get a registry containing the built-in modules.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            constructors: HashMap::new(),
+        }
+    }
+
+    /// Registers a module kind, replacing any existing module with the same kind.
+    pub fn register<M>(&mut self, kind: impl Into<String>)
+    where
+        M: Module + DeserializeOwned + 'static,
+    {
+        self.constructors
+            .insert(kind.into(), |config| Ok(Box::new(config.try_into::<M>()?)));
+    }
+
+    /// Returns the registered module kinds.
+    pub fn kinds(&self) -> impl Iterator<Item = &str> {
+        self.constructors.keys().map(String::as_str)
+    }
+
+    /// Builds a module from its config.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the module kind is unknown or its config is invalid.
+    pub fn build(&self, config: config::Module) -> Result<Box<dyn Module>, toml::de::Error> {
+        let constructor = self.constructors.get(&config.kind).ok_or_else(|| {
+            toml::de::Error::custom(format!("unknown module kind `{}`", config.kind))
+        })?;
+
+        constructor(toml::Value::Table(config.config)).map_err(|error| {
+            toml::de::Error::custom(format!("invalid `{}` module: {error}", config.kind))
+        })
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        #[allow(unused_mut)]
+        let mut registry = Self::new();
+
+        #[cfg(feature = "clock")]
+        registry.register::<self::clock::Clock>("clock");
+        #[cfg(feature = "bandwidth")]
+        registry.register::<self::bandwidth::Bandwidth>("bandwidth");
+        #[cfg(feature = "battery")]
+        registry.register::<self::battery::Battery>("battery");
+        #[cfg(feature = "bluetooth")]
+        registry.register::<self::bluetooth::Bluetooth>("bluetooth");
+        #[cfg(feature = "bluetooth")]
+        registry.register::<self::bluetooth::BluetoothDevice>("bluetooth_device");
+        #[cfg(feature = "cpu")]
+        registry.register::<self::cpu::Cpu>("cpu");
+        #[cfg(feature = "disk")]
+        registry.register::<self::disk::Disk>("disk");
+        #[cfg(feature = "load")]
+        registry.register::<self::load::Load>("load");
+        #[cfg(feature = "memory")]
+        registry.register::<self::memory::Memory>("memory");
+        #[cfg(feature = "network")]
+        registry.register::<self::network::Network>("network");
+        #[cfg(feature = "temperature")]
+        registry.register::<self::temperature::Temperature>("temperature");
+        #[cfg(feature = "wifi")]
+        registry.register::<self::wifi::Config>("wifi");
+
+        registry
+    }
 }
@@ -0,0 +1,291 @@
+//! Network bandwidth status bar module.
+
+use crate::{
+    block::Block,
+    click::Clicks,
+    modules::Module,
+    template::{Template, Vars},
+    thresholds::Thresholds,
+    units::{self, as_f64},
+};
+use async_stream::stream;
+use futures_util::{stream::LocalBoxStream, Stream, StreamExt};
+use serde::Deserialize;
+use std::{fmt, io, time::Duration};
+use tokio::{
+    fs,
+    time::{Instant, MissedTickBehavior},
+};
+
+/// The file containing the counters of every network interface.
+const NET_DEV: &str = "/proc/net/dev";
+
+/// Network bandwidth status bar module, showing how fast interfaces receive and transmit data.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Bandwidth {
+    /// The names of the interfaces to show, or every interface except `lo` when this is empty.
+    pub interfaces: Vec<String>,
+    /// Whether to show each interface separately, instead of the total of every interface.
+    pub per_interface: bool,
+    /// The text between interfaces when they're shown separately.
+    pub separator: String,
+    /// How often to measure the bandwidth, in seconds.
+    pub interval: u64,
+    /// The text to display, for the total or for each interface.
+    ///
+    /// The variables are:
+    ///
+    /// - `icon`
+    /// - `interface`, the name of the interface, only set when interfaces are shown separately.
+    /// - `rx` and `tx`, the receive and transmit rates with a unit, like `1.2 MiB/s`.
+    /// - `rx_rate` and `tx_rate`, the receive and transmit rates in bytes per second.
+    pub format: Template,
+    /// The text to display when the bar is shortened, with the same variables as `format`.
+    pub short_format: Template,
+    /// The icon of the block.
+    pub icon: String,
+    /// The color of the block, either a color like `#00ff00` or the name of a color from the
+    /// [`Theme`](crate::theme::Theme).
+    ///
+    /// When this isn't set, the color of the state of the block is used.
+    pub color: Option<String>,
+    /// The thresholds for the state of the block, based on the higher of the receive and transmit
+    /// rates in bytes per second.
+    ///
+    /// When interfaces are shown separately, the busiest interface decides the state.
+    pub thresholds: Thresholds,
+    /// Whether to hide the block when there's an error, instead of showing the error.
+    pub hide_errors: bool,
+}
+
+impl Bandwidth {
+    /// Returns a stream of block updates.
+    ///
+    /// The first block is shown after the bandwidth has been measured once, which takes
+    /// `interval` seconds.
+    pub fn stream(self) -> impl Stream<Item = Option<Block>> {
+        stream! {
+            let mut interval = tokio::time::interval(Duration::from_secs(self.interval.max(1)));
+            interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+            let mut previous: Option<(Instant, Vec<(String, Counters)>)> = None;
+
+            loop {
+                interval.tick().await;
+
+                let now = Instant::now();
+
+                match self.read().await {
+                    Ok(counters) => {
+                        if let Some((then, previous)) = &previous {
+                            yield Some(self.block(&rates(&counters, previous, now - *then)));
+                        }
+
+                        previous = Some((now, counters));
+                    }
+                    Err(error) => {
+                        previous = None;
+                        yield self.error_block(&error);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reads the counters of the interfaces to show.
+    async fn read(&self) -> io::Result<Vec<(String, Counters)>> {
+        let counters = read_counters().await?;
+
+        if self.interfaces.is_empty() {
+            return Ok(counters
+                .into_iter()
+                .filter(|(interface, _)| interface != "lo")
+                .collect());
+        }
+
+        self.interfaces
+            .iter()
+            .map(|interface| {
+                counters
+                    .iter()
+                    .find(|(name, _)| name == interface)
+                    .cloned()
+                    .ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::NotFound,
+                            format!("interface `{interface}` not found"),
+                        )
+                    })
+            })
+            .collect()
+    }
+
+    fn block(&self, rates: &[(&str, Rates)]) -> Block {
+        let busiest = rates
+            .iter()
+            .map(|(_, rates)| rates.rx.max(rates.tx))
+            .fold(0.0, f64::max);
+
+        let (text, short_text) = if self.per_interface {
+            let vars = rates
+                .iter()
+                .map(|(interface, rates)| self.vars(rates).with("interface", *interface))
+                .collect::<Vec<_>>();
+
+            let render = |template: &Template| {
+                vars.iter()
+                    .map(|vars| template.render(vars))
+                    .collect::<Vec<_>>()
+                    .join(&self.separator)
+            };
+
+            (render(&self.format), render(&self.short_format))
+        } else {
+            let total = rates
+                .iter()
+                .fold(Rates::default(), |total, (_, rates)| Rates {
+                    rx: total.rx + rates.rx,
+                    tx: total.tx + rates.tx,
+                });
+
+            let vars = self.vars(&total);
+
+            (self.format.render(&vars), self.short_format.render(&vars))
+        };
+
+        Block {
+            text,
+            short_text: Some(short_text),
+            color: self.color.clone(),
+            state: self.thresholds.state_of_high(busiest),
+            ..Block::default()
+        }
+    }
+
+    fn vars(&self, rates: &Rates) -> Vars {
+        Vars::new()
+            .with("icon", self.icon.as_str())
+            .with("rx", format!("{}/s", units::bytes(rates.rx)))
+            .with("tx", format!("{}/s", units::bytes(rates.tx)))
+            .with("rx_rate", rates.rx)
+            .with("tx_rate", rates.tx)
+    }
+
+    fn error_block(&self, error: &impl fmt::Display) -> Option<Block> {
+        (!self.hide_errors).then(|| Block::error(format!("bandwidth: {error}")))
+    }
+}
+
+impl Module for Bandwidth {
+    fn stream(self: Box<Self>, _clicks: Clicks) -> LocalBoxStream<'static, Option<Block>> {
+        Self::stream(*self).boxed_local()
+    }
+}
+
+impl Default for Bandwidth {
+    fn default() -> Self {
+        Self {
+            interfaces: Vec::new(),
+            per_interface: false,
+            separator: " ".into(),
+            interval: 1,
+            format: "{icon} \u{2193}{rx} \u{2191}{tx}"
+                .parse()
+                .expect("valid template"),
+            short_format: "\u{2193}{rx} \u{2191}{tx}".parse().expect("valid template"),
+            icon: "\u{f0ec}".into(),
+            color: None,
+            thresholds: Thresholds::default(),
+            hide_errors: false,
+        }
+    }
+}
+
+/// The number of bytes an interface has received and transmitted since it was created.
+#[derive(Debug, Clone, Copy)]
+struct Counters {
+    rx: u64,
+    tx: u64,
+}
+
+/// The rates an interface receives and transmits at, in bytes per second.
+#[derive(Debug, Clone, Copy, Default)]
+struct Rates {
+    rx: f64,
+    tx: f64,
+}
+
+/// Reads the counters of every interface.
+///
+/// After two header lines, each line is the name of an interface followed by a colon, 8 receive
+/// counters starting with the bytes, and 8 transmit counters starting with the bytes.
+async fn read_counters() -> io::Result<Vec<(String, Counters)>> {
+    let net_dev = fs::read_to_string(NET_DEV).await?;
+
+    net_dev
+        .lines()
+        .skip(2)
+        .map(|line| {
+            let invalid = || {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid line in `{NET_DEV}`: {line}"),
+                )
+            };
+
+            let (interface, counters) = line.split_once(':').ok_or_else(invalid)?;
+            let counters = counters.split_whitespace().collect::<Vec<_>>();
+
+            let counter = |index: usize| -> io::Result<u64> {
+                counters
+                    .get(index)
+                    .and_then(|counter| counter.parse().ok())
+                    .ok_or_else(invalid)
+            };
+
+            Ok((
+                interface.trim().to_owned(),
+                Counters {
+                    rx: counter(0)?,
+                    tx: counter(8)?,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Calculates the rate of each interface from its counters now and `elapsed` ago.
+///
+/// Interfaces that didn't exist before are skipped, and counters that went down, for example
+/// because the interface was recreated, count as no traffic.
+fn rates<'a>(
+    counters: &'a [(String, Counters)],
+    previous: &[(String, Counters)],
+    elapsed: Duration,
+) -> Vec<(&'a str, Rates)> {
+    counters
+        .iter()
+        .filter_map(|(interface, counters)| {
+            let (_, previous) = previous.iter().find(|(name, _)| name == interface)?;
+
+            Some((
+                interface.as_str(),
+                Rates {
+                    rx: rate(counters.rx.saturating_sub(previous.rx), elapsed),
+                    tx: rate(counters.tx.saturating_sub(previous.tx), elapsed),
+                },
+            ))
+        })
+        .collect()
+}
+
+fn rate(bytes: u64, elapsed: Duration) -> f64 {
+    let seconds = elapsed.as_secs_f64();
+
+    if seconds > 0.0 {
+        as_f64(bytes) / seconds
+    } else {
+        0.0
+    }
+}
@@ -1,97 +1,318 @@
 //! Battery status bar module.
 
-use crate::block::Block;
+mod sysfs;
+mod upower;
+
+use crate::{
+    block::{Block, State as BlockState},
+    click::Clicks,
+    dbus,
+    modules::Module,
+    template::{Template, Vars},
+    thresholds::Thresholds,
+};
 use async_stream::stream;
-use futures_util::Stream;
+use futures_util::{stream::LocalBoxStream, Stream, StreamExt};
 use serde::Deserialize;
-use std::{
-    path::{Path, PathBuf},
-    time::Duration,
-};
-use tokio::{
-    fs,
-    time::{Interval, MissedTickBehavior},
-};
+use std::{fmt, time::Duration};
 
 /// Battery status bar module.
 #[derive(Deserialize)]
 #[serde(default)]
 pub struct Battery {
-    /// The name of the battery.
+    /// Where to read the batteries from.
+    pub backend: Backend,
+    /// The names of the batteries to combine with the sysfs backend.
+    ///
+    /// These are the names found in `/sys/class/power_supply`, which should look like `BATX`
+    /// where `X` is an integer. When this is empty, every system battery is combined. upower
+    /// always combines every system battery, so sysfs is used whenever batteries are listed.
+    pub batteries: Vec<String>,
+    /// The name of a single battery to read with the sysfs backend.
     ///
-    /// This is the name found in `/sys/class/power_supply`, which should look like `BATX` where
-    /// `X` is an integer.
-    pub name: String,
+    /// This is deprecated, and is the same as listing the battery in `batteries`.
+    pub name: Option<String>,
+    /// The text to display.
+    ///
+    /// The variables are:
+    ///
+    /// - `icon`
+    /// - `capacity`, the combined capacity of the batteries as a percentage.
+    /// - `status`, for example `Charging` or `Not charging`.
+    /// - `time`, the estimated time until the batteries are empty or full, like `1:05`.
+    /// - `power`, the power drawn from or put into the batteries in watts.
+    /// - `ac`, either `online` or `offline` when there is an AC adapter, which includes USB-C
+    ///   chargers.
+    pub format: Template,
+    /// The text to display when the bar is shortened, with the same variables as `format`.
+    pub short_format: Template,
+    /// The icon for each state of the battery.
+    pub icons: Icons,
+    /// The color for each state of the battery.
+    pub colors: Colors,
+    /// The thresholds for the state of the block, based on the capacity when the batteries are
+    /// discharging.
+    ///
+    /// A charging battery is always in the good state.
+    pub thresholds: Thresholds,
+    /// Whether to hide the block when there's an error, instead of showing the error.
+    pub hide_errors: bool,
+}
+
+/// Where to read the batteries from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+    /// upower, which reports changes as they happen.
+    ///
+    /// sysfs is used instead while upower isn't running, or when `batteries` lists batteries.
+    #[default]
+    Upower,
+    /// sysfs, which is read every 10 seconds.
+    Sysfs,
+}
+
+/// The icon for each state of a battery.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Icons {
+    /// The icon when the battery is charging.
+    pub charging: String,
+    /// The icon when the AC adapter is online but the battery isn't charging, for example when
+    /// it's full.
+    pub plugged: String,
+    /// The icon when the capacity is at most 25%.
+    pub quarter: String,
+    /// The icon when the capacity is at most 50%.
+    pub half: String,
+    /// The icon when the capacity is at most 75%.
+    pub three_quarters: String,
+    /// The icon when the capacity is above 75%.
+    pub full: String,
+}
+
+impl Default for Icons {
+    fn default() -> Self {
+        Self {
+            charging: "\u{f1e6}".into(),
+            plugged: "\u{f1e6}".into(),
+            quarter: "\u{f243}".into(),
+            half: "\u{f242}".into(),
+            three_quarters: "\u{f241}".into(),
+            full: "\u{f240}".into(),
+        }
+    }
+}
+
+/// The color for each state of a battery, either a color like `#00ff00` or the name of a color
+/// from the [`Theme`](crate::theme::Theme).
+///
+/// When a color isn't set, the color of the state of the block is used.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct Colors {
+    /// The color when the battery is charging.
+    pub charging: Option<String>,
+    /// The color when the AC adapter is online but the battery isn't charging.
+    pub plugged: Option<String>,
 }
 
 impl Battery {
     /// Returns a stream of block updates.
-    pub fn stream(self) -> impl Stream<Item = Option<Block>> {
+    ///
+    /// With the upower backend, sysfs is read instead when upower isn't running, until it starts.
+    /// sysfs is read every 10 seconds, and whenever the kernel reports a change to a power supply.
+    pub fn stream(mut self) -> impl Stream<Item = Option<Block>> {
+        self.batteries.extend(self.name.take());
+
         stream! {
-            let mut state = State {
-                interval: {
-                    let mut interval = tokio::time::interval(Duration::from_secs(10));
-                    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
-                    interval
-                },
-                capacity_path: Path::new("/sys/class/power_supply").join(&self.name).join("capacity"),
-                status_path: Path::new("/sys/class/power_supply").join(&self.name).join("status"),
+            // upower always combines every system battery, so listed batteries are read from
+            // sysfs.
+            let upower = match self.backend {
+                Backend::Upower if self.batteries.is_empty() => upower::connect().await.ok(),
+                Backend::Upower | Backend::Sysfs => None,
+            };
+
+            let (connection, mut owner_changes) = match upower {
+                Some(upower) => upower,
+                None => {
+                    let mut changes = sysfs::Changes::new();
+
+                    loop {
+                        yield self.read_sysfs().await;
+                        changes.next().await;
+                    }
+                }
             };
 
             loop {
-                yield state.next().await;
+                let error = match upower::DisplayDevice::new(&connection).await {
+                    Ok(mut device) => loop {
+                        yield match device.info().await {
+                            Ok(info) => Some(self.block(&info)),
+                            Err(error) => self.error_block(&error),
+                        };
+
+                        if let Err(error) = device.wait_for_change(&mut owner_changes).await {
+                            break error;
+                        }
+                    },
+                    Err(error) => error,
+                };
+
+                if matches!(error, dbus::Error::Restarted) {
+                    continue;
+                }
+
+                // Read sysfs until upower starts again.
+                let mut changes = sysfs::Changes::new();
+
+                loop {
+                    yield self.read_sysfs().await;
+
+                    if has_started(&mut changes, &mut owner_changes).await {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    async fn read_sysfs(&self) -> Option<Block> {
+        match sysfs::read(&self.batteries).await {
+            Ok(info) => Some(self.block(&info)),
+            Err(error) => self.error_block(&error),
+        }
+    }
+
+    fn block(&self, info: &Info) -> Block {
+        // Without an AC adapter, a battery that's full or not charging is assumed to be plugged in.
+        let is_plugged = info
+            .ac_online
+            .unwrap_or(matches!(info.status, Status::Full | Status::NotCharging));
+
+        let (icon, color, state) = match info.status {
+            Status::Charging => (
+                &self.icons.charging,
+                &self.colors.charging,
+                Some(BlockState::Good),
+            ),
+            _ if is_plugged => (&self.icons.plugged, &self.colors.plugged, None),
+            _ => {
+                let icon = match info.capacity {
+                    0..=25 => &self.icons.quarter,
+                    26..=50 => &self.icons.half,
+                    51..=75 => &self.icons.three_quarters,
+                    _ => &self.icons.full,
+                };
+
+                let state = self.thresholds.state_of_low(info.capacity.into());
+
+                (icon, &None, state)
             }
+        };
+
+        let time = info.time.map(|time| {
+            let minutes = time.as_secs() / 60;
+            format!("{}:{:02}", minutes / 60, minutes % 60)
+        });
+
+        let vars = Vars::new()
+            .with("icon", icon.as_str())
+            .with("capacity", info.capacity)
+            .with("status", info.status.to_string())
+            .with_option("time", time)
+            .with_option("power", info.power)
+            .with_option(
+                "ac",
+                info.ac_online
+                    .map(|online| if online { "online" } else { "offline" }),
+            );
+
+        Block {
+            text: self.format.render(&vars),
+            short_text: Some(self.short_format.render(&vars)),
+            color: color.clone(),
+            percentage: Some(info.capacity),
+            state,
+            ..Block::default()
         }
     }
+
+    fn error_block(&self, error: &impl fmt::Display) -> Option<Block> {
+        (!self.hide_errors).then(|| Block::error(format!("battery: {error}")))
+    }
+}
+
+impl Module for Battery {
+    fn stream(self: Box<Self>, _clicks: Clicks) -> LocalBoxStream<'static, Option<Block>> {
+        Self::stream(*self).boxed_local()
+    }
 }
 
 impl Default for Battery {
     fn default() -> Self {
         Self {
-            name: "BAT0".into(),
+            backend: Backend::default(),
+            batteries: Vec::new(),
+            name: None,
+            format: "{icon} {capacity}%".parse().expect("valid template"),
+            short_format: "{icon} {capacity}%".parse().expect("valid template"),
+            icons: Icons::default(),
+            colors: Colors::default(),
+            thresholds: Thresholds {
+                critical: Some(15.0),
+                ..Thresholds::default()
+            },
+            hide_errors: false,
         }
     }
 }
 
-struct State {
-    interval: Interval,
-    capacity_path: PathBuf,
-    status_path: PathBuf,
+/// The combined state of the batteries.
+struct Info {
+    /// The capacity as a percentage.
+    capacity: u8,
+    status: Status,
+    /// Whether the AC adapter is online, if there is one.
+    ac_online: Option<bool>,
+    /// The power drawn from or put into the batteries in watts.
+    power: Option<f64>,
+    /// The estimated time until the batteries are empty when discharging, or full when charging.
+    time: Option<Duration>,
 }
 
-impl State {
-    async fn next(&mut self) -> Option<Block> {
-        self.interval.tick().await;
-
-        let capacity = fs::read_to_string(&self.capacity_path)
-            .await
-            .unwrap()
-            .trim()
-            .parse::<u8>()
-            .unwrap();
-
-        let status = fs::read_to_string(&self.status_path).await.unwrap();
-
-        let is_charging = status.trim() == "Charging";
-
-        let icon = match (is_charging, capacity) {
-            (true, _) => "",
-            (false, 0..=25) => "",
-            (false, 26..=50) => "",
-            (false, 51..=75) => "",
-            _ => "",
-        };
+/// The status of a battery.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Charging,
+    Discharging,
+    Full,
+    NotCharging,
+    Unknown,
+}
 
-        let color = if is_charging {
-            Some("#00ff00".into())
-        } else {
-            (capacity <= 15).then_some("#ff0000".into())
-        };
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Charging => write!(f, "Charging"),
+            Self::Discharging => write!(f, "Discharging"),
+            Self::Full => write!(f, "Full"),
+            Self::NotCharging => write!(f, "Not charging"),
+            Self::Unknown => write!(f, "Unknown"),
+        }
+    }
+}
 
-        Some(Block {
-            text: format!("{icon} {capacity}%"),
-            short_text: Some(format!("{icon} {capacity}%")),
-            color,
-        })
+/// Waits until a power supply may have changed, returning whether upower started in the meantime.
+async fn has_started(changes: &mut sysfs::Changes, owner_changes: &mut dbus::OwnerChanges) -> bool {
+    tokio::select! {
+        () = changes.next() => false,
+        Some(true) = owner_changes.next() => true,
     }
 }
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+const fn clamp_percentage(percentage: f64) -> u8 {
+    percentage.round().clamp(0.0, 100.0) as u8
+}
@@ -0,0 +1,430 @@
+//! Reading batteries and AC adapters from sysfs.
+
+use super::{clamp_percentage, Info, Status};
+use futures_util::future::OptionFuture;
+use netlink_sys::{
+    protocols::NETLINK_KOBJECT_UEVENT, AsyncSocket, AsyncSocketExt, SocketAddr, TokioSocket,
+};
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tokio::{
+    fs,
+    time::{Instant, Interval, MissedTickBehavior},
+};
+
+/// The directory containing every power supply.
+const POWER_SUPPLY: &str = "/sys/class/power_supply";
+
+/// How often to read the batteries, to catch changes that don't cause a uevent.
+const PERIOD: Duration = Duration::from_secs(10);
+
+/// The netlink multicast group of uevents sent by the kernel.
+const KERNEL_UEVENTS: u32 = 1;
+
+/// Changes to power supplies.
+///
+/// The kernel sends a uevent when a power supply changes state, for example when the AC adapter is
+/// plugged in, but not for every change in capacity, so power supplies are also read periodically.
+pub struct Changes {
+    interval: Interval,
+    /// Kernel uevents, which may not be available, for example in containers.
+    uevents: Option<TokioSocket>,
+}
+
+impl Changes {
+    /// Creates a new instance of [`Changes`].
+    pub fn new() -> Self {
+        let mut interval = tokio::time::interval_at(Instant::now() + PERIOD, PERIOD);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        Self {
+            interval,
+            uevents: uevents().ok(),
+        }
+    }
+
+    /// Waits until a power supply may have changed.
+    pub async fn next(&mut self) {
+        let uevent: OptionFuture<_> = self.uevents.as_mut().map(next_uevent).into();
+
+        tokio::select! {
+            _ = self.interval.tick() => {}
+            Some(Ok(())) = uevent => {}
+        }
+    }
+}
+
+/// Opens a socket receiving kernel uevents.
+fn uevents() -> io::Result<TokioSocket> {
+    let mut socket = TokioSocket::new(NETLINK_KOBJECT_UEVENT)?;
+    socket
+        .socket_mut()
+        .bind(&SocketAddr::new(0, KERNEL_UEVENTS))?;
+    Ok(socket)
+}
+
+/// Waits for a uevent of a power supply.
+///
+/// A uevent is a header like `change@/devices/...` followed by `KEY=value` pairs, each ending
+/// with a nul byte.
+async fn next_uevent(socket: &mut TokioSocket) -> io::Result<()> {
+    loop {
+        let (message, _) = socket.recv_from_full().await?;
+
+        if message
+            .split(|&byte| byte == 0)
+            .any(|field| field == b"SUBSYSTEM=power_supply")
+        {
+            return Ok(());
+        }
+    }
+}
+
+/// A single battery.
+struct Battery {
+    status: Status,
+    capacity: u8,
+    /// The energy stored in the battery in µWh.
+    energy_now: Option<f64>,
+    /// The energy stored in the battery when it's full in µWh.
+    energy_full: Option<f64>,
+    /// The power drawn from or put into the battery in µW.
+    power: Option<f64>,
+}
+
+impl Battery {
+    async fn read(path: &Path) -> io::Result<Self> {
+        let status = match read_attribute(path, "status").await?.as_deref() {
+            Some("Charging") => Status::Charging,
+            Some("Discharging") => Status::Discharging,
+            Some("Full") => Status::Full,
+            Some("Not charging") => Status::NotCharging,
+            _ => Status::Unknown,
+        };
+
+        let capacity = read_number(path, "capacity").await.ok().flatten();
+
+        // Batteries report either energy in µWh and power in µW, or charge in µAh and current in
+        // µA, which is converted to energy and power with the voltage.
+        let voltage = match read_number(path, "voltage_now").await.ok().flatten() {
+            Some(voltage) => Some(voltage),
+            None => read_number(path, "voltage_min_design").await.ok().flatten(),
+        }
+        .map(|voltage| voltage / 1_000_000.0);
+
+        let energy_now = read_energy(path, "energy_now", "charge_now", voltage).await;
+        let energy_full = read_energy(path, "energy_full", "charge_full", voltage).await;
+        let power = read_energy(path, "power_now", "current_now", voltage)
+            .await
+            .map(f64::abs);
+
+        let capacity = match (capacity, energy_now, energy_full) {
+            (Some(capacity), ..) => capacity,
+            (None, Some(now), Some(full)) if full > 0.0 => now / full * 100.0,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("cannot read the capacity of `{}`", path.display()),
+                ))
+            }
+        };
+
+        Ok(Self {
+            status,
+            capacity: clamp_percentage(capacity),
+            energy_now,
+            energy_full,
+            power,
+        })
+    }
+}
+
+/// Reads and combines the batteries with the given names, or every system battery if `names` is
+/// empty.
+pub async fn read(names: &[String]) -> io::Result<Info> {
+    let mut batteries = Vec::new();
+    let mut ac_online = None;
+
+    for path in power_supplies().await? {
+        match read_attribute(&path, "type").await?.as_deref() {
+            Some("Battery") => {
+                if !names.is_empty() {
+                    continue;
+                }
+
+                // Batteries of peripherals such as mice have a device scope.
+                let is_system = read_attribute(&path, "scope").await?.as_deref() != Some("Device");
+                let is_present = read_number(&path, "present").await? != Some(0.0);
+
+                if is_system && is_present {
+                    batteries.push(Battery::read(&path).await?);
+                }
+            }
+            // Any other supply with an online state powers the system while it's online, whether
+            // it's a mains adapter or a USB-C charger. USB supplies report 2 when they're online
+            // with a negotiated voltage.
+            Some(_) => {
+                if let Some(online) = read_number(&path, "online").await? {
+                    ac_online = Some(ac_online.unwrap_or(false) || online > 0.0);
+                }
+            }
+            None => {}
+        }
+    }
+
+    for name in names {
+        let path = Path::new(POWER_SUPPLY).join(name);
+
+        if read_attribute(&path, "type").await?.is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("battery `{name}` not found"),
+            ));
+        }
+
+        batteries.push(Battery::read(&path).await?);
+    }
+
+    if batteries.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "no batteries found",
+        ));
+    }
+
+    Ok(combine(&batteries, ac_online))
+}
+
+/// Combines batteries into one, weighting their capacities by their energy when it's known.
+fn combine(batteries: &[Battery], ac_online: Option<bool>) -> Info {
+    let any = |status| batteries.iter().any(|battery| battery.status == status);
+
+    let status = if any(Status::Charging) {
+        Status::Charging
+    } else if any(Status::Discharging) {
+        Status::Discharging
+    } else if batteries
+        .iter()
+        .all(|battery| battery.status == Status::Full)
+    {
+        Status::Full
+    } else if any(Status::NotCharging) {
+        Status::NotCharging
+    } else {
+        Status::Unknown
+    };
+
+    let energy_now = batteries
+        .iter()
+        .map(|battery| battery.energy_now)
+        .sum::<Option<f64>>();
+
+    let energy_full = batteries
+        .iter()
+        .map(|battery| battery.energy_full)
+        .sum::<Option<f64>>();
+
+    let capacity = match (energy_now, energy_full) {
+        (Some(now), Some(full)) if full > 0.0 => clamp_percentage(now / full * 100.0),
+        _ => {
+            let total = batteries
+                .iter()
+                .map(|battery| f64::from(battery.capacity))
+                .sum::<f64>();
+
+            let count = u32::try_from(batteries.len()).unwrap_or(u32::MAX);
+
+            clamp_percentage(total / f64::from(count))
+        }
+    };
+
+    let power = batteries
+        .iter()
+        .filter_map(|battery| battery.power)
+        .reduce(|total, power| total + power);
+
+    let remaining = match status {
+        Status::Charging => energy_full.zip(energy_now).map(|(full, now)| full - now),
+        Status::Discharging => energy_now,
+        _ => None,
+    };
+
+    let time = remaining
+        .zip(power)
+        .filter(|&(_, power)| power > 0.0)
+        .and_then(|(energy, power)| Duration::try_from_secs_f64(energy / power * 3600.0).ok());
+
+    Info {
+        capacity,
+        status,
+        ac_online,
+        power: power.map(|power| power / 1_000_000.0),
+        time,
+    }
+}
+
+/// Returns the path of every power supply.
+async fn power_supplies() -> io::Result<Vec<PathBuf>> {
+    let mut power_supplies = Vec::new();
+    let mut entries = fs::read_dir(POWER_SUPPLY).await?;
+
+    while let Some(entry) = entries.next_entry().await? {
+        power_supplies.push(entry.path());
+    }
+
+    Ok(power_supplies)
+}
+
+/// Reads an attribute of a power supply, returning `None` if it doesn't exist.
+async fn read_attribute(path: &Path, attribute: &str) -> io::Result<Option<String>> {
+    let path = path.join(attribute);
+
+    match fs::read_to_string(&path).await {
+        Ok(value) => Ok(Some(value.trim().to_owned())),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(io::Error::new(
+            error.kind(),
+            format!("cannot read `{}`: {error}", path.display()),
+        )),
+    }
+}
+
+/// Reads a numeric attribute of a power supply, returning `None` if it doesn't exist.
+async fn read_number(path: &Path, attribute: &str) -> io::Result<Option<f64>> {
+    read_attribute(path, attribute)
+        .await?
+        .map(|value| {
+            value.parse().map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid `{attribute}` in `{}`", path.display()),
+                )
+            })
+        })
+        .transpose()
+}
+
+/// Reads an energy or power attribute, falling back to the equivalent charge or current attribute
+/// multiplied by the voltage.
+///
+/// Drivers fail to read some attributes while the battery is changing state, so errors are
+/// treated as the attribute being unknown.
+async fn read_energy(path: &Path, energy: &str, charge: &str, voltage: Option<f64>) -> Option<f64> {
+    match read_number(path, energy).await.ok().flatten() {
+        Some(energy) => Some(energy),
+        None => read_number(path, charge)
+            .await
+            .ok()
+            .flatten()
+            .zip(voltage)
+            .map(|(charge, voltage)| charge * voltage),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn battery(status: Status, capacity: u8, energy: Option<(f64, f64)>, power: f64) -> Battery {
+        Battery {
+            status,
+            capacity,
+            energy_now: energy.map(|(now, _)| now),
+            energy_full: energy.map(|(_, full)| full),
+            power: Some(power),
+        }
+    }
+
+    #[test]
+    fn capacity_is_weighted_by_energy() {
+        let info = combine(
+            &[
+                battery(Status::Discharging, 100, Some((20.0, 20.0)), 0.0),
+                battery(Status::Discharging, 0, Some((0.0, 60.0)), 0.0),
+            ],
+            None,
+        );
+
+        assert_eq!(info.capacity, 25);
+    }
+
+    #[test]
+    fn capacity_is_averaged_without_energy() {
+        let info = combine(
+            &[
+                battery(Status::Discharging, 100, Some((20.0, 20.0)), 0.0),
+                battery(Status::Discharging, 0, None, 0.0),
+            ],
+            None,
+        );
+
+        assert_eq!(info.capacity, 50);
+    }
+
+    #[test]
+    fn time_until_empty() {
+        let info = combine(
+            &[
+                battery(Status::Discharging, 50, Some((30.0, 60.0)), 5.0),
+                battery(Status::Full, 100, Some((30.0, 30.0)), 5.0),
+            ],
+            Some(false),
+        );
+
+        assert_eq!(info.status, Status::Discharging);
+        assert_eq!(info.ac_online, Some(false));
+        assert_eq!(info.time, Some(Duration::from_hours(6)));
+        assert_eq!(info.power, Some(0.000_01));
+    }
+
+    #[test]
+    fn time_until_full() {
+        let info = combine(
+            &[battery(Status::Charging, 25, Some((15.0, 60.0)), 30.0)],
+            Some(true),
+        );
+
+        assert_eq!(info.status, Status::Charging);
+        assert_eq!(info.time, Some(Duration::from_mins(90)));
+    }
+
+    #[test]
+    fn no_time_without_power() {
+        let info = combine(
+            &[battery(Status::Discharging, 50, Some((30.0, 60.0)), 0.0)],
+            None,
+        );
+
+        assert_eq!(info.time, None);
+    }
+
+    #[test]
+    fn status_is_merged() {
+        let status = |statuses: &[Status]| {
+            let batteries = statuses
+                .iter()
+                .map(|&status| battery(status, 50, None, 0.0))
+                .collect::<Vec<_>>();
+
+            combine(&batteries, None).status
+        };
+
+        assert_eq!(
+            status(&[Status::Discharging, Status::Charging]),
+            Status::Charging
+        );
+        assert_eq!(
+            status(&[Status::Full, Status::Discharging]),
+            Status::Discharging
+        );
+        assert_eq!(status(&[Status::Full, Status::Full]), Status::Full);
+        assert_eq!(
+            status(&[Status::Full, Status::NotCharging]),
+            Status::NotCharging
+        );
+        assert_eq!(status(&[Status::Full, Status::Unknown]), Status::Unknown);
+    }
+}
@@ -0,0 +1,158 @@
+//! Reading the batteries from upower.
+
+use super::{Info, Status};
+use crate::dbus;
+use futures_util::StreamExt;
+use std::time::Duration;
+use zbus::{
+    fdo::{PropertiesChangedStream, PropertiesProxy},
+    CacheProperties, Connection,
+};
+
+/// Connects to the system bus, and starts watching for changes to the owner of upower.
+pub async fn connect() -> Result<(Connection, dbus::OwnerChanges), dbus::Error> {
+    let connection = Connection::system().await?;
+    let owner_changes = dbus::owner_changes(&connection, "org.freedesktop.UPower").await?;
+    Ok((connection, owner_changes))
+}
+
+/// The device upower combines every system battery into.
+pub struct DisplayDevice<'a> {
+    device: interfaces::DeviceProxy<'a>,
+    device_changes: PropertiesChangedStream<'a>,
+    upower: interfaces::UPowerProxy<'a>,
+    upower_changes: PropertiesChangedStream<'a>,
+}
+
+impl<'a> DisplayDevice<'a> {
+    /// Creates a new instance of [`DisplayDevice`], starting upower if needed.
+    pub async fn new(connection: &'a Connection) -> Result<DisplayDevice<'a>, dbus::Error> {
+        // Properties are read after each change instead of being cached, as the cache may not be
+        // updated yet when the change is received.
+        let device = interfaces::DeviceProxy::builder(connection)
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await?;
+
+        let upower = interfaces::UPowerProxy::builder(connection)
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await?;
+
+        let device_changes = PropertiesProxy::builder(connection)
+            .destination(device.destination().to_owned())?
+            .path(device.path().to_owned())?
+            .build()
+            .await?
+            .receive_properties_changed()
+            .await?;
+
+        let upower_changes = PropertiesProxy::builder(connection)
+            .destination(upower.destination().to_owned())?
+            .path(upower.path().to_owned())?
+            .build()
+            .await?
+            .receive_properties_changed()
+            .await?;
+
+        // Fail early when upower isn't available.
+        device.is_present().await?;
+
+        Ok(Self {
+            device,
+            device_changes,
+            upower,
+            upower_changes,
+        })
+    }
+
+    /// Reads the current state of the batteries.
+    pub async fn info(&self) -> Result<Info, dbus::Error> {
+        if !self.device.is_present().await? {
+            return Err(dbus::Error::NotFound("battery".into()));
+        }
+
+        // See https://upower.freedesktop.org/docs/Device.html#Device:State.
+        let status = match self.device.state().await? {
+            1 => Status::Charging,
+            2 | 3 => Status::Discharging,
+            4 => Status::Full,
+            5 | 6 => Status::NotCharging,
+            _ => Status::Unknown,
+        };
+
+        let time = match status {
+            Status::Charging => self.device.time_to_full().await?,
+            Status::Discharging => self.device.time_to_empty().await?,
+            _ => 0,
+        };
+
+        let power = self.device.energy_rate().await?;
+
+        Ok(Info {
+            capacity: super::clamp_percentage(self.device.percentage().await?),
+            status,
+            ac_online: Some(!self.upower.on_battery().await?),
+            power: (power > 0.0).then_some(power),
+            time: u64::try_from(time)
+                .ok()
+                .filter(|&time| time > 0)
+                .map(Duration::from_secs),
+        })
+    }
+
+    /// Waits until the state of the batteries changes.
+    pub async fn wait_for_change(
+        &mut self,
+        owner_changes: &mut dbus::OwnerChanges,
+    ) -> Result<(), dbus::Error> {
+        tokio::select! {
+            Some(has_owner) = owner_changes.next() => {
+                return Err(dbus::owner_change_error(has_owner));
+            }
+
+            Some(_) = self.device_changes.next() => {}
+
+            Some(_) = self.upower_changes.next() => {}
+        }
+
+        Ok(())
+    }
+}
+
+mod interfaces {
+    #[zbus::dbus_proxy(
+        interface = "org.freedesktop.UPower",
+        default_service = "org.freedesktop.UPower",
+        default_path = "/org/freedesktop/UPower"
+    )]
+    trait UPower {
+        #[dbus_proxy(property)]
+        fn on_battery(&self) -> zbus::Result<bool>;
+    }
+
+    #[zbus::dbus_proxy(
+        interface = "org.freedesktop.UPower.Device",
+        default_service = "org.freedesktop.UPower",
+        default_path = "/org/freedesktop/UPower/devices/DisplayDevice"
+    )]
+    trait Device {
+        #[dbus_proxy(property)]
+        fn is_present(&self) -> zbus::Result<bool>;
+
+        #[dbus_proxy(property)]
+        fn percentage(&self) -> zbus::Result<f64>;
+
+        #[dbus_proxy(property)]
+        fn state(&self) -> zbus::Result<u32>;
+
+        #[dbus_proxy(property)]
+        fn time_to_empty(&self) -> zbus::Result<i64>;
+
+        #[dbus_proxy(property)]
+        fn time_to_full(&self) -> zbus::Result<i64>;
+
+        #[dbus_proxy(property)]
+        fn energy_rate(&self) -> zbus::Result<f64>;
+    }
+}
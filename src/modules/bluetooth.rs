@@ -1,109 +1,305 @@
 //! Bluetooth status bar module.
 
-use crate::{block::Block, dbus};
+use crate::{
+    block::{Block, State as BlockState},
+    click::{Button, Clicks},
+    dbus,
+    modules::Module,
+    template::{Template, Vars},
+    thresholds::Thresholds,
+};
 use async_stream::stream;
-use futures_util::{Stream, StreamExt};
+use futures_util::{stream::LocalBoxStream, Stream, StreamExt};
 use serde::Deserialize;
-use zbus::{fdo::ObjectManagerProxy, zvariant::ObjectPath, Connection, PropertyStream};
+use zbus::{zvariant::ObjectPath, Connection, PropertyStream};
 
 /// Bluetooth status bar module.
 #[derive(Deserialize)]
 pub struct Bluetooth {
     /// The address of the bluetooth adapter.
     pub address: String,
+    /// The text to display.
+    ///
+    /// The only variable is `icon`.
+    #[serde(default = "default_adapter_format")]
+    pub format: Template,
+    /// The text to display when the bar is shortened, with the same variables as `format`.
+    #[serde(default = "default_adapter_format")]
+    pub short_format: Template,
+    /// The icon of the adapter.
+    #[serde(default = "default_adapter_icon")]
+    pub icon: String,
+    /// The icon of the adapter while it's powered off.
+    #[serde(default = "default_adapter_icon")]
+    pub off_icon: String,
+    /// The color of the block, either a color like `#0000ff` or the name of a color from the
+    /// [`Theme`](crate::theme::Theme).
+    #[serde(default)]
+    pub color: Option<String>,
+    /// The color of the block while the adapter is powered off.
+    ///
+    /// When this isn't set, the color of the idle state is used.
+    #[serde(default)]
+    pub off_color: Option<String>,
+    /// Whether to hide the block while the adapter is powered off.
+    ///
+    /// The block can't be clicked to power the adapter back on while it's hidden.
+    #[serde(default)]
+    pub hide_when_off: bool,
+    /// Whether to hide the block when there's an error, instead of showing the error.
+    #[serde(default)]
+    pub hide_errors: bool,
 }
 
 impl Bluetooth {
     /// Returns a stream of block updates.
     ///
-    /// # Panics
-    ///
-    /// TODO
-    pub fn stream(self) -> impl Stream<Item = Option<Block>> {
+    /// Clicking the block toggles the power of the adapter. When bluez stops or restarts, the
+    /// adapter is reconnected to once bluez is running again, and when the adapter can't be
+    /// found, it's looked for again whenever bluez adds an object.
+    pub fn stream(self, mut clicks: Clicks) -> impl Stream<Item = Option<Block>> {
         stream! {
-            let connection = Connection::system().await.unwrap();
-
-            let object_manager = ObjectManagerProxy::builder(&connection)
-                .destination("org.bluez")
-                .unwrap()
-                .path("/")
-                .unwrap()
-                .build()
-                .await
-                .unwrap();
-
-            let path = dbus::find_path(
-                &object_manager,
-                "org.bluez.Adapter1",
-                "Address",
-                self.address.as_str(),
-            )
-            .await
-            .unwrap()
-            .unwrap();
-
-            let mut adapter = Adapter::connect(&connection, path.as_ref()).await.unwrap();
+            let (connection, mut owner_changes) = match connect().await {
+                Ok(connection) => connection,
+                Err(error) => {
+                    yield error_block(self.hide_errors, &error);
+                    return;
+                }
+            };
 
             loop {
-                yield adapter.block();
-                adapter.wait_for_change().await.unwrap();
+                // Objects are watched before looking for the adapter, so one added in between
+                // isn't missed.
+                let mut objects_added = dbus::objects_added(&connection, "org.bluez", "/")
+                    .await
+                    .ok();
+
+                let error = match Adapter::find(&connection, &self.address).await {
+                    Ok(mut adapter) => {
+                        // The objects added are no longer needed, and would otherwise pile up.
+                        objects_added = None;
+
+                        loop {
+                            yield adapter.block(&self);
+
+                            let change = adapter.wait_for_change(&mut clicks, &mut owner_changes);
+
+                            if let Err(error) = change.await {
+                                break error;
+                            }
+                        }
+                    }
+                    Err(error) => error,
+                };
+
+                match error {
+                    dbus::Error::Restarted => {}
+                    dbus::Error::Stopped => {
+                        yield error_block(self.hide_errors, &error);
+
+                        if !dbus::wait_for_owner(&mut owner_changes).await {
+                            return;
+                        }
+                    }
+                    error => {
+                        yield error_block(self.hide_errors, &error);
+
+                        if !dbus::wait_for_object(objects_added.as_mut(), &mut owner_changes)
+                            .await
+                        {
+                            return;
+                        }
+                    }
+                }
             }
         }
     }
 }
 
+impl Module for Bluetooth {
+    fn stream(self: Box<Self>, clicks: Clicks) -> LocalBoxStream<'static, Option<Block>> {
+        Self::stream(*self, clicks).boxed_local()
+    }
+}
+
 /// Bluetooth device status bar module.
 #[derive(Deserialize)]
 pub struct BluetoothDevice {
     /// The address of the bluetooth device.
     pub address: String,
+    /// The text to display.
+    ///
+    /// The variables are `icon`, `alias` and `battery`, which is the battery percentage of the
+    /// device when it's known.
+    #[serde(default = "default_device_format")]
+    pub format: Template,
+    /// The text to display when the bar is shortened, with the same variables as `format`.
+    #[serde(default = "default_device_format")]
+    pub short_format: Template,
+    /// The icon for each kind of device.
+    #[serde(default)]
+    pub icons: DeviceIcons,
+    /// The color of the block, either a color like `#0000ff` or the name of a color from the
+    /// [`Theme`](crate::theme::Theme).
+    ///
+    /// When this isn't set, the color of the state of the block is used.
+    #[serde(default)]
+    pub color: Option<String>,
+    /// The thresholds for the state of the block, based on the battery percentage of the device.
+    #[serde(default = "default_device_thresholds")]
+    pub thresholds: Thresholds,
+    /// Whether to hide the block when there's an error, instead of showing the error.
+    #[serde(default)]
+    pub hide_errors: bool,
 }
 
 impl BluetoothDevice {
     /// Returns a stream of block updates.
     ///
-    /// # Panics
-    ///
-    /// TODO
+    /// When bluez stops or restarts, the device is reconnected to once bluez is running again, and
+    /// when the device can't be found, it's looked for again whenever bluez adds an object, such
+    /// as a newly paired device.
     pub fn stream(self) -> impl Stream<Item = Option<Block>> {
         stream! {
-            let connection = Connection::system().await.unwrap();
-
-            let object_manager = ObjectManagerProxy::builder(&connection)
-                .destination("org.bluez")
-                .unwrap()
-                .path("/")
-                .unwrap()
-                .build()
-                .await
-                .unwrap();
-
-            let path = dbus::find_path(
-                &object_manager,
-                "org.bluez.Device1",
-                "Address",
-                self.address.as_str(),
-            )
-            .await
-            .unwrap()
-            .unwrap();
-
-            let mut device = Device::connect(&connection, path.as_ref()).await.unwrap();
+            let (connection, mut owner_changes) = match connect().await {
+                Ok(connection) => connection,
+                Err(error) => {
+                    yield error_block(self.hide_errors, &error);
+                    return;
+                }
+            };
 
             loop {
-                yield device.block();
-                device.wait_for_change().await.unwrap();
+                // Objects are watched before looking for the device, so one added in between
+                // isn't missed.
+                let mut objects_added = dbus::objects_added(&connection, "org.bluez", "/")
+                    .await
+                    .ok();
+
+                let error = match Device::find(&connection, &self.address).await {
+                    Ok(mut device) => {
+                        // The objects added are no longer needed, and would otherwise pile up.
+                        objects_added = None;
+
+                        loop {
+                            yield device.block(&self);
+
+                            if let Err(error) = device.wait_for_change(&mut owner_changes).await {
+                                break error;
+                            }
+                        }
+                    }
+                    Err(error) => error,
+                };
+
+                match error {
+                    dbus::Error::Restarted => {}
+                    dbus::Error::Stopped => {
+                        yield error_block(self.hide_errors, &error);
+
+                        if !dbus::wait_for_owner(&mut owner_changes).await {
+                            return;
+                        }
+                    }
+                    error => {
+                        yield error_block(self.hide_errors, &error);
+
+                        if !dbus::wait_for_object(objects_added.as_mut(), &mut owner_changes)
+                            .await
+                        {
+                            return;
+                        }
+                    }
+                }
             }
         }
     }
 }
 
+impl Module for BluetoothDevice {
+    fn stream(self: Box<Self>, _clicks: Clicks) -> LocalBoxStream<'static, Option<Block>> {
+        Self::stream(*self).boxed_local()
+    }
+}
+
+/// Connects to the system bus, and starts watching for changes to the owner of bluez.
+async fn connect() -> Result<(Connection, dbus::OwnerChanges), dbus::Error> {
+    let connection = Connection::system().await?;
+    let owner_changes = dbus::owner_changes(&connection, "org.bluez").await?;
+    Ok((connection, owner_changes))
+}
+
+/// The icon for each kind of bluetooth device, as reported by bluez.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct DeviceIcons {
+    /// The icon for audio devices, such as headsets.
+    pub audio: String,
+    /// The icon for game controllers.
+    pub gaming: String,
+    /// The icon for keyboards.
+    pub keyboard: String,
+    /// The icon for mice.
+    pub mouse: String,
+    /// The icon for any other kind of device.
+    pub other: String,
+}
+
+impl Default for DeviceIcons {
+    fn default() -> Self {
+        Self {
+            audio: "\u{f025}".into(),
+            gaming: "\u{f11b}".into(),
+            keyboard: "\u{f11c}".into(),
+            mouse: "\u{f8cc}".into(),
+            other: "\u{f293}".into(),
+        }
+    }
+}
+
+const fn default_device_thresholds() -> Thresholds {
+    Thresholds {
+        good: None,
+        info: None,
+        warning: None,
+        critical: Some(15.0),
+    }
+}
+
+fn default_adapter_icon() -> String {
+    "\u{f294}".into()
+}
+
+fn default_adapter_format() -> Template {
+    "{icon}".parse().expect("valid template")
+}
+
+fn default_device_format() -> Template {
+    "{icon}[ {battery}]".parse().expect("valid template")
+}
+
+/// Returns the block to show for an error.
+fn error_block(hide_errors: bool, error: &dbus::Error) -> Option<Block> {
+    (!hide_errors).then(|| Block::error(format!("bluetooth: {error}")))
+}
+
 struct Adapter<'a> {
+    proxy: interfaces::AdapterProxy<'a>,
     powered: bool,
     powered_changes: PropertyStream<'a, bool>,
 }
 
 impl<'a> Adapter<'a> {
+    async fn find(connection: &'a Connection, address: &str) -> Result<Adapter<'a>, dbus::Error> {
+        let object_manager = dbus::object_manager(connection, "org.bluez").await?;
+
+        let path = dbus::find_path(&object_manager, "org.bluez.Adapter1", "Address", address)
+            .await?
+            .ok_or_else(|| dbus::Error::NotFound(format!("adapter {address}")))?;
+
+        Ok(Self::connect(connection, path.into_inner()).await?)
+    }
+
     async fn connect(
         connection: &'a Connection,
         path: ObjectPath<'a>,
@@ -116,20 +312,54 @@ impl<'a> Adapter<'a> {
         Ok(Self {
             powered: adapter.powered().await?,
             powered_changes: adapter.receive_powered_changed().await,
+            proxy: adapter,
         })
     }
 
-    fn block(&self) -> Option<Block> {
-        self.powered.then_some(Block {
-            text: "".into(),
-            short_text: Some("".into()),
-            color: None,
+    fn block(&self, config: &Bluetooth) -> Option<Block> {
+        if !self.powered && config.hide_when_off {
+            return None;
+        }
+
+        let (icon, color, state) = if self.powered {
+            (&config.icon, &config.color, None)
+        } else {
+            (&config.off_icon, &config.off_color, Some(BlockState::Idle))
+        };
+
+        let vars = Vars::new().with("icon", icon.as_str());
+
+        Some(Block {
+            text: config.format.render(&vars),
+            short_text: Some(config.short_format.render(&vars)),
+            color: color.clone(),
+            state,
+            ..Block::default()
         })
     }
 
-    async fn wait_for_change(&mut self) -> zbus::Result<()> {
-        if let Some(change) = self.powered_changes.next().await {
-            self.powered = change.get().await?;
+    async fn wait_for_change(
+        &mut self,
+        clicks: &mut Clicks,
+        owner_changes: &mut dbus::OwnerChanges,
+    ) -> Result<(), dbus::Error> {
+        tokio::select! {
+            Some(has_owner) = owner_changes.next() => {
+                return Err(dbus::owner_change_error(has_owner));
+            }
+
+            Some(change) = self.powered_changes.next() => {
+                self.powered = change.get().await?;
+            }
+
+            Some(click) = clicks.recv() => {
+                if click.button == Button::Left {
+                    // The power change is reported back through `powered_changes`, so a failure
+                    // here, for example when the radio is blocked by rfkill, simply leaves the
+                    // block as it is.
+                    let _ = self.proxy.set_powered(!self.powered).await;
+                }
+            }
         }
 
         Ok(())
@@ -148,6 +378,16 @@ struct Device<'a> {
 }
 
 impl<'a> Device<'a> {
+    async fn find(connection: &'a Connection, address: &str) -> Result<Device<'a>, dbus::Error> {
+        let object_manager = dbus::object_manager(connection, "org.bluez").await?;
+
+        let path = dbus::find_path(&object_manager, "org.bluez.Device1", "Address", address)
+            .await?
+            .ok_or_else(|| dbus::Error::NotFound(format!("device {address}")))?;
+
+        Ok(Self::connect(connection, path.into_inner()).await?)
+    }
+
     async fn connect(connection: &'a Connection, path: ObjectPath<'a>) -> zbus::Result<Device<'a>> {
         let device = interfaces::DeviceProxy::builder(connection)
             .path(&path)?
@@ -171,32 +411,46 @@ impl<'a> Device<'a> {
         })
     }
 
-    fn block(&self) -> Option<Block> {
+    fn block(&self, config: &BluetoothDevice) -> Option<Block> {
         if !self.connected {
             return None;
         }
 
         let icon = match self.icon.as_ref() {
-            "audio-card" | "audio-headset" => "",
-            "input-gaming" => "",
-            "input-keyboard" => "",
-            "input-mouse" => "",
-            _ => "",
+            "audio-card" | "audio-headset" => &config.icons.audio,
+            "input-gaming" => &config.icons.gaming,
+            "input-keyboard" => &config.icons.keyboard,
+            "input-mouse" => &config.icons.mouse,
+            _ => &config.icons.other,
         };
 
-        let text = self
-            .battery_percentage
-            .map_or_else(|| icon.into(), |percentage| format!("{icon} {percentage}"));
+        let vars = Vars::new()
+            .with("icon", icon.as_str())
+            .with("alias", self.alias.as_str())
+            .with_option("battery", self.battery_percentage);
 
         Some(Block {
-            text: text.clone(),
-            short_text: Some(text),
-            color: None,
+            text: config.format.render(&vars),
+            short_text: Some(config.short_format.render(&vars)),
+            color: config.color.clone(),
+            tooltip: Some(self.alias.clone()),
+            percentage: self.battery_percentage,
+            state: self
+                .battery_percentage
+                .and_then(|percentage| config.thresholds.state_of_low(percentage.into())),
+            ..Block::default()
         })
     }
 
-    async fn wait_for_change(&mut self) -> zbus::Result<()> {
+    async fn wait_for_change(
+        &mut self,
+        owner_changes: &mut dbus::OwnerChanges,
+    ) -> Result<(), dbus::Error> {
         tokio::select! {
+            Some(has_owner) = owner_changes.next() => {
+                return Err(dbus::owner_change_error(has_owner));
+            }
+
             Some(change) = self.alias_changes.next() => {
                 self.alias = change.get().await?;
             }
@@ -223,6 +477,9 @@ mod interfaces {
     trait Adapter {
         #[dbus_proxy(property)]
         fn powered(&self) -> zbus::Result<bool>;
+
+        #[dbus_proxy(property)]
+        fn set_powered(&self, value: bool) -> zbus::Result<()>;
     }
 
     #[zbus::dbus_proxy(interface = "org.bluez.Device1", default_service = "org.bluez")]
@@ -1,9 +1,14 @@
 //! Clock status bar module.
 
-use crate::block::Block;
+use crate::{
+    block::Block,
+    click::{self, Button, Clicks},
+    modules::Module,
+    template::{Template, Vars},
+};
 use async_stream::stream;
 use chrono::Local;
-use futures_util::Stream;
+use futures_util::{stream::LocalBoxStream, Stream, StreamExt};
 use serde::Deserialize;
 use std::time::Duration;
 use tokio::time::{Interval, MissedTickBehavior};
@@ -12,15 +17,24 @@ use tokio::time::{Interval, MissedTickBehavior};
 #[derive(Deserialize)]
 #[serde(default)]
 pub struct Clock {
-    /// The date and time format to display.
-    pub format: String,
-    /// The date and time format to display when the bar is shortened.
-    pub short_format: String,
+    /// The text to display.
+    ///
+    /// The variables are `time` and `short_time`, the date and time formatted with `time_format`
+    /// and `short_time_format`.
+    pub format: Template,
+    /// The text to display when the bar is shortened, with the same variables as `format`.
+    pub short_format: Template,
+    /// The format of the `time` variable, as described in [`chrono::format::strftime`].
+    pub time_format: String,
+    /// The format of the `short_time` variable, as described in [`chrono::format::strftime`].
+    pub short_time_format: String,
+    /// A shell command to run when the clock is clicked, for example to open a calendar.
+    pub on_click: Option<String>,
 }
 
 impl Clock {
     /// Returns a stream of block updates.
-    pub fn stream(self) -> impl Stream<Item = Option<Block>> {
+    pub fn stream(self, clicks: Clicks) -> impl Stream<Item = Option<Block>> {
         stream! {
             let mut state = State {
                 interval: {
@@ -30,6 +44,10 @@ impl Clock {
                 },
                 format: self.format,
                 short_format: self.short_format,
+                time_format: self.time_format,
+                short_time_format: self.short_time_format,
+                on_click: self.on_click,
+                clicks,
             };
 
             loop {
@@ -39,31 +57,62 @@ impl Clock {
     }
 }
 
+impl Module for Clock {
+    fn stream(self: Box<Self>, clicks: Clicks) -> LocalBoxStream<'static, Option<Block>> {
+        Self::stream(*self, clicks).boxed_local()
+    }
+}
+
 impl Default for Clock {
     fn default() -> Self {
         Self {
-            format: "%Y-%m-%d %H:%M:%S".into(),
-            short_format: "%H:%M".into(),
+            format: "{time}".parse().expect("valid template"),
+            short_format: "{short_time}".parse().expect("valid template"),
+            time_format: "%Y-%m-%d %H:%M:%S".into(),
+            short_time_format: "%H:%M".into(),
+            on_click: None,
         }
     }
 }
 
 struct State {
     interval: Interval,
-    format: String,
-    short_format: String,
+    format: Template,
+    short_format: Template,
+    time_format: String,
+    short_time_format: String,
+    on_click: Option<String>,
+    clicks: Clicks,
 }
 
 impl State {
     async fn next(&mut self) -> Option<Block> {
-        self.interval.tick().await;
+        loop {
+            tokio::select! {
+                _ = self.interval.tick() => break,
+
+                Some(click) = self.clicks.recv() => {
+                    if let (Button::Left, Some(command)) = (click.button, &self.on_click) {
+                        click::spawn_command(command);
+                    }
+                }
+            }
+        }
 
         let now = Local::now();
 
+        let vars = Vars::new()
+            .with("time", now.format(&self.time_format).to_string())
+            .with(
+                "short_time",
+                now.format(&self.short_time_format).to_string(),
+            );
+
         Some(Block {
-            text: format!("{}", now.format(&self.format)),
-            short_text: Some(format!("{}", now.format(&self.short_format))),
+            text: self.format.render(&vars),
+            short_text: Some(self.short_format.render(&vars)),
             color: None,
+            ..Block::default()
         })
     }
 }
@@ -0,0 +1,298 @@
+//! CPU usage status bar module.
+
+use crate::{
+    block::Block,
+    click::Clicks,
+    modules::Module,
+    template::{Template, Vars},
+    thresholds::Thresholds,
+};
+use async_stream::stream;
+use futures_util::{stream::LocalBoxStream, Stream, StreamExt};
+use serde::Deserialize;
+use std::{fmt, io, time::Duration};
+use tokio::{fs, time::MissedTickBehavior};
+
+/// The file containing the time spent by each CPU in each state.
+const STAT: &str = "/proc/stat";
+
+/// The glyphs of the graph, from no usage to full usage.
+const BARS: [char; 8] = [
+    '\u{2581}', '\u{2582}', '\u{2583}', '\u{2584}', '\u{2585}', '\u{2586}', '\u{2587}', '\u{2588}',
+];
+
+/// CPU usage status bar module.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Cpu {
+    /// How often to measure the usage, in seconds.
+    pub interval: u64,
+    /// The text to display.
+    ///
+    /// The variables are:
+    ///
+    /// - `icon`
+    /// - `usage`, the usage of every core together as a percentage.
+    /// - `graph`, the usage of each core as a bar, like `▁▃▇█`.
+    /// - `cores`, the number of cores.
+    pub format: Template,
+    /// The text to display when the bar is shortened, with the same variables as `format`.
+    pub short_format: Template,
+    /// The icon of the block.
+    pub icon: String,
+    /// The color of the block, either a color like `#00ff00` or the name of a color from the
+    /// [`Theme`](crate::theme::Theme).
+    ///
+    /// When this isn't set, the color of the state of the block is used.
+    pub color: Option<String>,
+    /// The thresholds for the state of the block, based on the usage as a percentage.
+    pub thresholds: Thresholds,
+    /// Whether to hide the block when there's an error, instead of showing the error.
+    pub hide_errors: bool,
+}
+
+impl Cpu {
+    /// Returns a stream of block updates.
+    ///
+    /// The first block is shown after the usage has been measured once, which takes `interval`
+    /// seconds.
+    pub fn stream(self) -> impl Stream<Item = Option<Block>> {
+        stream! {
+            let mut interval = tokio::time::interval(Duration::from_secs(self.interval.max(1)));
+            interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+            let mut previous: Option<Stat> = None;
+
+            loop {
+                interval.tick().await;
+
+                match Stat::read().await {
+                    Ok(stat) => {
+                        if let Some(previous) = &previous {
+                            yield Some(self.block(&stat, previous));
+                        }
+
+                        previous = Some(stat);
+                    }
+                    Err(error) => {
+                        previous = None;
+                        yield self.error_block(&error);
+                    }
+                }
+            }
+        }
+    }
+
+    fn block(&self, stat: &Stat, previous: &Stat) -> Block {
+        let usage = stat.total.usage_since(&previous.total);
+
+        let vars = Vars::new()
+            .with("icon", self.icon.as_str())
+            .with("usage", usage)
+            .with("graph", stat.graph(previous))
+            .with("cores", u64::try_from(stat.cores.len()).unwrap_or(u64::MAX));
+
+        Block {
+            text: self.format.render(&vars),
+            short_text: Some(self.short_format.render(&vars)),
+            color: self.color.clone(),
+            percentage: Some(usage),
+            state: self.thresholds.state_of_high(usage.into()),
+            ..Block::default()
+        }
+    }
+
+    fn error_block(&self, error: &impl fmt::Display) -> Option<Block> {
+        (!self.hide_errors).then(|| Block::error(format!("cpu: {error}")))
+    }
+}
+
+impl Module for Cpu {
+    fn stream(self: Box<Self>, _clicks: Clicks) -> LocalBoxStream<'static, Option<Block>> {
+        Self::stream(*self).boxed_local()
+    }
+}
+
+impl Default for Cpu {
+    fn default() -> Self {
+        Self {
+            interval: 1,
+            format: "{icon} {usage}%".parse().expect("valid template"),
+            short_format: "{usage}%".parse().expect("valid template"),
+            icon: "\u{f2db}".into(),
+            color: None,
+            thresholds: Thresholds::default(),
+            hide_errors: false,
+        }
+    }
+}
+
+/// The time spent by every core together and by each core, with its number.
+struct Stat {
+    total: Times,
+    cores: Vec<(usize, Times)>,
+}
+
+impl Stat {
+    /// Reads the times from `/proc/stat`, where the line of every core together starts with `cpu`
+    /// and the line of each core starts with `cpu` followed by its number.
+    async fn read() -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(STAT).await?)
+    }
+
+    fn parse(stat: &str) -> io::Result<Self> {
+        let mut total = None;
+        let mut cores = Vec::new();
+
+        for line in stat.lines() {
+            let mut fields = line.split_whitespace();
+
+            match fields.next() {
+                Some("cpu") => total = Some(Times::parse(fields)?),
+                Some(name) => {
+                    if let Some(number) = name
+                        .strip_prefix("cpu")
+                        .and_then(|number| number.parse().ok())
+                    {
+                        cores.push((number, Times::parse(fields)?));
+                    }
+                }
+                None => {}
+            }
+        }
+
+        let total = total.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("no cpu line in `{STAT}`"),
+            )
+        })?;
+
+        Ok(Self { total, cores })
+    }
+
+    /// Returns the usage of each core since `previous` as a graph of bars.
+    ///
+    /// Cores are matched by their number, as cores going offline or online shift the others. Cores
+    /// that just came online have no previous times, so they're skipped until the next read.
+    fn graph(&self, previous: &Self) -> String {
+        self.cores
+            .iter()
+            .filter_map(|(number, core)| {
+                let (_, previous) = previous.cores.iter().find(|(other, _)| other == number)?;
+                Some(bar(core.usage_since(previous)))
+            })
+            .collect()
+    }
+}
+
+/// The time a CPU has spent busy and in total since boot, in clock ticks.
+#[derive(Debug, Clone, Copy)]
+struct Times {
+    busy: u64,
+    total: u64,
+}
+
+impl Times {
+    /// Parses the times of a CPU, which are user, nice, system, idle, iowait, irq, softirq and
+    /// steal, followed by guest times that are already part of user and nice.
+    fn parse<'a>(fields: impl Iterator<Item = &'a str>) -> io::Result<Self> {
+        let times = fields
+            .take(8)
+            .map(str::parse)
+            .collect::<Result<Vec<u64>, _>>()
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("invalid `{STAT}`")))?;
+
+        // The time spent idle or waiting for I/O.
+        let idle = times.iter().skip(3).take(2).sum::<u64>();
+        let total = times.iter().sum();
+
+        Ok(Self {
+            busy: total - idle,
+            total,
+        })
+    }
+
+    /// Returns the percentage of time spent busy since `previous`.
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    fn usage_since(&self, previous: &Self) -> u8 {
+        let busy = self.busy.saturating_sub(previous.busy);
+        let total = self.total.saturating_sub(previous.total);
+
+        if total == 0 {
+            return 0;
+        }
+
+        (busy as f64 / total as f64 * 100.0)
+            .round()
+            .clamp(0.0, 100.0) as u8
+    }
+}
+
+/// Returns the bar glyph of a percentage.
+fn bar(usage: u8) -> char {
+    let index = (usize::from(usage) * (BARS.len() - 1) + 50) / 100;
+    BARS[index.min(BARS.len() - 1)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let stat = Stat::parse(
+            "cpu  40 0 10 40 10 0 0 0 5 0\n\
+             cpu0 20 0 5 20 5 0 0 0 5 0\n\
+             cpu2 20 0 5 20 5 0 0 0 0 0\n\
+             intr 12345 0 0\n\
+             ctxt 6789\n",
+        )
+        .expect("valid stat");
+
+        assert_eq!((stat.total.busy, stat.total.total), (50, 100));
+        assert_eq!(
+            stat.cores
+                .iter()
+                .map(|(number, times)| (*number, times.busy, times.total))
+                .collect::<Vec<_>>(),
+            [(0, 25, 50), (2, 25, 50)]
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(Stat::parse("cpu0 1 2 3 4 5 6 7 8\n").is_err());
+        assert!(Stat::parse("cpu  1 2 x 4 5 6 7 8\n").is_err());
+    }
+
+    #[test]
+    fn graph_matches_cores_by_number() {
+        let previous = Stat::parse(
+            "cpu  0 0 0 0 0 0 0 0\n\
+             cpu0 0 0 0 0 0 0 0 0\n\
+             cpu1 100 0 0 0 0 0 0 0\n\
+             cpu2 0 0 0 0 0 0 0 0\n",
+        )
+        .expect("valid stat");
+
+        // cpu1 went offline, so cpu2 comes second and must still be compared with cpu2, and cpu3
+        // came online, so it has no previous times.
+        let stat = Stat::parse(
+            "cpu  300 0 0 100 0 0 0 0\n\
+             cpu0 0 0 0 100 0 0 0 0\n\
+             cpu2 100 0 0 0 0 0 0 0\n\
+             cpu3 200 0 0 0 0 0 0 0\n",
+        )
+        .expect("valid stat");
+
+        assert_eq!(
+            stat.graph(&previous),
+            format!("{}{}", BARS[0], BARS[BARS.len() - 1])
+        );
+    }
+}
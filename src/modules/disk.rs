@@ -0,0 +1,272 @@
+//! Disk space status bar module.
+
+use crate::{
+    block::Block,
+    click::Clicks,
+    modules::Module,
+    template::{Template, Vars},
+    thresholds::Thresholds,
+    units::{self, as_f64},
+};
+use async_stream::stream;
+use futures_util::{stream::LocalBoxStream, Stream, StreamExt};
+use nix::sys::statvfs::statvfs;
+use serde::Deserialize;
+use std::{fmt, fs, io, time::Duration};
+use tokio::time::MissedTickBehavior;
+
+/// The file listing every mounted filesystem.
+const MOUNTS: &str = "/proc/self/mounts";
+
+/// The file listing every filesystem type known to the kernel.
+const FILESYSTEMS: &str = "/proc/filesystems";
+
+/// Disk space status bar module, showing the space of one or more filesystems.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Disk {
+    /// The mount points of the filesystems to show.
+    pub mount_points: Vec<String>,
+    /// The types of filesystems to show, like `ext4`, instead of the filesystems in
+    /// `mount_points`.
+    ///
+    /// Every mounted filesystem of these types that can be read is shown. The type `*` matches
+    /// every filesystem stored on a device, which excludes pseudo filesystems like `proc` and
+    /// `tmpfs`.
+    pub fs_types: Vec<String>,
+    /// The text between filesystems.
+    pub separator: String,
+    /// How often to read the space of the filesystems, in seconds.
+    pub interval: u64,
+    /// The text to display for each filesystem.
+    ///
+    /// The variables are:
+    ///
+    /// - `icon`
+    /// - `mount_point`, where the filesystem is mounted.
+    /// - `fs_type`, the type of the filesystem, when filesystems are listed by type.
+    /// - `total`, `used` and `free`, amounts of space with a unit, like `1.5 GiB`. The free space
+    ///   is the space available to users other than root.
+    /// - `used_percent` and `free_percent`, the used and free space as a percentage of the space
+    ///   available to users other than root, like `df` shows.
+    pub format: Template,
+    /// The text to display when the bar is shortened, with the same variables as `format`.
+    pub short_format: Template,
+    /// The icon of the block.
+    pub icon: String,
+    /// The color of the block, either a color like `#00ff00` or the name of a color from the
+    /// [`Theme`](crate::theme::Theme).
+    ///
+    /// When this isn't set, the color of the state of the block is used.
+    pub color: Option<String>,
+    /// The thresholds for the state of the block, based on the used space as a percentage.
+    ///
+    /// The fullest filesystem decides the state.
+    pub thresholds: Thresholds,
+    /// Whether to hide the block when there's an error, instead of showing the error.
+    pub hide_errors: bool,
+}
+
+impl Disk {
+    /// Returns a stream of block updates.
+    pub fn stream(self) -> impl Stream<Item = Option<Block>> {
+        stream! {
+            let mut interval = tokio::time::interval(Duration::from_secs(self.interval.max(1)));
+            interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+            loop {
+                interval.tick().await;
+
+                let mount_points = self.mount_points.clone();
+                let fs_types = self.fs_types.clone();
+
+                // Reading the space of a filesystem can block, for example when a network
+                // filesystem is unreachable.
+                let spaces = tokio::task::spawn_blocking(move || read(&mount_points, &fs_types))
+                    .await
+                    .unwrap_or_else(|error| Err(io::Error::other(error)));
+
+                yield match spaces {
+                    Ok(spaces) => Some(self.block(&spaces)),
+                    Err(error) => self.error_block(&error),
+                };
+            }
+        }
+    }
+
+    fn block(&self, spaces: &[Space]) -> Block {
+        let fullest = spaces.iter().map(Space::used_percent).max();
+
+        let vars = spaces
+            .iter()
+            .map(|space| {
+                Vars::new()
+                    .with("icon", self.icon.as_str())
+                    .with("mount_point", space.mount_point.as_str())
+                    .with_option("fs_type", space.fs_type.as_deref())
+                    .with("total", units::bytes(as_f64(space.total)))
+                    .with("used", units::bytes(as_f64(space.used)))
+                    .with("free", units::bytes(as_f64(space.free)))
+                    .with("used_percent", space.used_percent())
+                    .with("free_percent", 100 - space.used_percent())
+            })
+            .collect::<Vec<_>>();
+
+        let render = |template: &Template| {
+            vars.iter()
+                .map(|vars| template.render(vars))
+                .collect::<Vec<_>>()
+                .join(&self.separator)
+        };
+
+        Block {
+            text: render(&self.format),
+            short_text: Some(render(&self.short_format)),
+            color: self.color.clone(),
+            percentage: fullest,
+            state: fullest.and_then(|fullest| self.thresholds.state_of_high(fullest.into())),
+            ..Block::default()
+        }
+    }
+
+    fn error_block(&self, error: &impl fmt::Display) -> Option<Block> {
+        (!self.hide_errors).then(|| Block::error(format!("disk: {error}")))
+    }
+}
+
+impl Module for Disk {
+    fn stream(self: Box<Self>, _clicks: Clicks) -> LocalBoxStream<'static, Option<Block>> {
+        Self::stream(*self).boxed_local()
+    }
+}
+
+impl Default for Disk {
+    fn default() -> Self {
+        Self {
+            mount_points: vec!["/".into()],
+            fs_types: Vec::new(),
+            separator: " ".into(),
+            interval: 30,
+            format: "{icon} {free}".parse().expect("valid template"),
+            short_format: "{free}".parse().expect("valid template"),
+            icon: "\u{f0a0}".into(),
+            color: None,
+            thresholds: Thresholds::default(),
+            hide_errors: false,
+        }
+    }
+}
+
+/// The space of a filesystem, in bytes.
+struct Space {
+    mount_point: String,
+    fs_type: Option<String>,
+    total: u64,
+    used: u64,
+    /// The space available to users other than root, which excludes the space reserved for root.
+    free: u64,
+}
+
+impl Space {
+    fn read(mount_point: String, fs_type: Option<String>) -> io::Result<Self> {
+        let stat = statvfs(mount_point.as_str())
+            .map_err(|error| io::Error::other(format!("cannot read `{mount_point}`: {error}")))?;
+
+        let fragment_size = stat.fragment_size();
+
+        Ok(Self {
+            mount_point,
+            fs_type,
+            total: stat.blocks() * fragment_size,
+            used: stat.blocks().saturating_sub(stat.blocks_free()) * fragment_size,
+            free: stat.blocks_available() * fragment_size,
+        })
+    }
+
+    /// Returns the used space as a percentage of the space available to users other than root.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn used_percent(&self) -> u8 {
+        let available = self.used + self.free;
+
+        if available == 0 {
+            return 0;
+        }
+
+        (as_f64(self.used) / as_f64(available) * 100.0)
+            .ceil()
+            .clamp(0.0, 100.0) as u8
+    }
+}
+
+/// Reads the space of the filesystems at `mount_points`, or of every mounted filesystem with one
+/// of `fs_types` if it isn't empty.
+fn read(mount_points: &[String], fs_types: &[String]) -> io::Result<Vec<Space>> {
+    if fs_types.is_empty() {
+        return mount_points
+            .iter()
+            .map(|mount_point| Space::read(mount_point.clone(), None))
+            .collect();
+    }
+
+    let device_fs_types = if fs_types.iter().any(|fs_type| fs_type == "*") {
+        device_fs_types()?
+    } else {
+        Vec::new()
+    };
+
+    let mut sources = Vec::new();
+    let mut spaces = Vec::new();
+
+    for line in fs::read_to_string(MOUNTS)?.lines() {
+        let mut fields = line.split_whitespace();
+
+        let (Some(source), Some(mount_point), Some(fs_type)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+
+        let is_listed = fs_types.iter().any(|listed| listed == fs_type)
+            || device_fs_types.iter().any(|listed| listed == fs_type);
+
+        // A filesystem mounted more than once, for example with bind mounts, is only shown once.
+        if !is_listed || sources.contains(&source) {
+            continue;
+        }
+
+        // Filesystems that can't be read, for example because of their permissions, are skipped
+        // rather than hiding every other filesystem. The source is only recorded once it's read,
+        // so a later mount of the same filesystem can still be shown.
+        let Ok(space) = Space::read(unescape(mount_point), Some(fs_type.to_owned())) else {
+            continue;
+        };
+
+        sources.push(source);
+
+        // Filesystems without any space, such as `autofs` mount points, are hidden.
+        if space.total > 0 {
+            spaces.push(space);
+        }
+    }
+
+    Ok(spaces)
+}
+
+/// Returns the filesystem types that are stored on a device, which are listed in
+/// `/proc/filesystems` without `nodev`.
+fn device_fs_types() -> io::Result<Vec<String>> {
+    Ok(fs::read_to_string(FILESYSTEMS)?
+        .lines()
+        .filter(|line| !line.starts_with("nodev"))
+        .map(|line| line.trim().to_owned())
+        .collect())
+}
+
+/// Replaces the octal escapes of spaces, tabs, newlines and backslashes in a mount point.
+fn unescape(mount_point: &str) -> String {
+    mount_point
+        .replace("\\040", " ")
+        .replace("\\011", "\t")
+        .replace("\\012", "\n")
+        .replace("\\134", "\\")
+}
@@ -0,0 +1,215 @@
+//! System load status bar module.
+
+use crate::{
+    block::Block,
+    click::Clicks,
+    modules::Module,
+    template::{Template, Vars},
+    thresholds::Thresholds,
+    units::as_f64,
+};
+use async_stream::stream;
+use futures_util::{stream::LocalBoxStream, Stream, StreamExt};
+use serde::Deserialize;
+use std::{fmt, io, time::Duration};
+use tokio::{fs, time::MissedTickBehavior};
+
+/// The file containing the load averages and the number of processes.
+const LOADAVG: &str = "/proc/loadavg";
+
+/// The file containing the time since boot.
+const UPTIME: &str = "/proc/uptime";
+
+/// The file containing a line for each core, used to count them.
+const STAT: &str = "/proc/stat";
+
+/// System load status bar module, showing the load averages, uptime and number of processes.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Load {
+    /// How often to read the load, in seconds.
+    pub interval: u64,
+    /// Whether to divide the load averages by the number of cores, so a fully loaded system has a
+    /// load of 1 however many cores it has.
+    pub normalize: bool,
+    /// The text to display.
+    ///
+    /// The variables are:
+    ///
+    /// - `icon`
+    /// - `load1`, `load5` and `load15`, the load averages over 1, 5 and 15 minutes.
+    /// - `uptime`, the time since boot, like `2d 3h 14m`.
+    /// - `running`, the number of processes that are running or ready to run.
+    /// - `processes`, the total number of processes.
+    /// - `cores`, the number of cores.
+    pub format: Template,
+    /// The text to display when the bar is shortened, with the same variables as `format`.
+    pub short_format: Template,
+    /// The icon of the block.
+    pub icon: String,
+    /// The color of the block, either a color like `#00ff00` or the name of a color from the
+    /// [`Theme`](crate::theme::Theme).
+    ///
+    /// When this isn't set, the color of the state of the block is used.
+    pub color: Option<String>,
+    /// The thresholds for the state of the block, based on the load average over 1 minute.
+    ///
+    /// The load is normalized when `normalize` is set, so the same thresholds work on machines with
+    /// a different number of cores.
+    pub thresholds: Thresholds,
+    /// Whether to hide the block when there's an error, instead of showing the error.
+    pub hide_errors: bool,
+}
+
+impl Load {
+    /// Returns a stream of block updates.
+    pub fn stream(self) -> impl Stream<Item = Option<Block>> {
+        stream! {
+            let mut interval = tokio::time::interval(Duration::from_secs(self.interval.max(1)));
+            interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+            loop {
+                interval.tick().await;
+
+                yield match LoadAvg::read().await {
+                    Ok(loadavg) => Some(self.block(&loadavg)),
+                    Err(error) => self.error_block(&error),
+                };
+            }
+        }
+    }
+
+    fn block(&self, loadavg: &LoadAvg) -> Block {
+        let divisor = if self.normalize {
+            as_f64(loadavg.cores.max(1))
+        } else {
+            1.0
+        };
+
+        let [one, five, fifteen] = loadavg.loads.map(|load| load / divisor);
+
+        let vars = Vars::new()
+            .with("icon", self.icon.as_str())
+            .with("load1", one)
+            .with("load5", five)
+            .with("load15", fifteen)
+            .with("uptime", uptime(loadavg.uptime))
+            .with("running", loadavg.running)
+            .with("processes", loadavg.processes)
+            .with("cores", loadavg.cores);
+
+        Block {
+            text: self.format.render(&vars),
+            short_text: Some(self.short_format.render(&vars)),
+            color: self.color.clone(),
+            state: self.thresholds.state_of_high(one),
+            ..Block::default()
+        }
+    }
+
+    fn error_block(&self, error: &impl fmt::Display) -> Option<Block> {
+        (!self.hide_errors).then(|| Block::error(format!("load: {error}")))
+    }
+}
+
+impl Module for Load {
+    fn stream(self: Box<Self>, _clicks: Clicks) -> LocalBoxStream<'static, Option<Block>> {
+        Self::stream(*self).boxed_local()
+    }
+}
+
+impl Default for Load {
+    // The templates aren't format strings, so their format specs are fine.
+    #[allow(clippy::literal_string_with_formatting_args)]
+    fn default() -> Self {
+        Self {
+            interval: 5,
+            normalize: false,
+            format: "{icon} {load1:.2}".parse().expect("valid template"),
+            short_format: "{load1:.2}".parse().expect("valid template"),
+            icon: "\u{f3fd}".into(),
+            color: None,
+            thresholds: Thresholds::default(),
+            hide_errors: false,
+        }
+    }
+}
+
+/// The load of the system.
+struct LoadAvg {
+    /// The load averages over 1, 5 and 15 minutes.
+    loads: [f64; 3],
+    running: u64,
+    processes: u64,
+    cores: u64,
+    /// The time since boot, in seconds.
+    uptime: u64,
+}
+
+impl LoadAvg {
+    /// Reads the load from `/proc/loadavg`, which contains the 3 load averages, the running and
+    /// total number of processes separated by a slash, and the last process ID.
+    async fn read() -> io::Result<Self> {
+        let loadavg = fs::read_to_string(LOADAVG).await?;
+        let invalid =
+            |file: &str| io::Error::new(io::ErrorKind::InvalidData, format!("invalid `{file}`"));
+
+        let fields = loadavg.split_whitespace().collect::<Vec<_>>();
+
+        let load = |index: usize| -> io::Result<f64> {
+            fields
+                .get(index)
+                .and_then(|load| load.parse().ok())
+                .ok_or_else(|| invalid(LOADAVG))
+        };
+
+        let (running, processes) = fields
+            .get(3)
+            .and_then(|processes| processes.split_once('/'))
+            .and_then(|(running, processes)| Some((running.parse().ok()?, processes.parse().ok()?)))
+            .ok_or_else(|| invalid(LOADAVG))?;
+
+        // The uptime is in seconds with a fraction, followed by the time spent idle.
+        let uptime = fs::read_to_string(UPTIME)
+            .await?
+            .split_whitespace()
+            .next()
+            .and_then(|uptime| uptime.split('.').next())
+            .and_then(|uptime| uptime.parse().ok())
+            .ok_or_else(|| invalid(UPTIME))?;
+
+        // Every line starting with `cpu` followed by a number is a core.
+        let cores = fs::read_to_string(STAT)
+            .await?
+            .lines()
+            .filter(|line| {
+                line.strip_prefix("cpu")
+                    .is_some_and(|line| line.starts_with(|c: char| c.is_ascii_digit()))
+            })
+            .count();
+
+        Ok(Self {
+            loads: [load(0)?, load(1)?, load(2)?],
+            running,
+            processes,
+            cores: u64::try_from(cores).unwrap_or(u64::MAX),
+            uptime,
+        })
+    }
+}
+
+/// Formats a number of seconds as days, hours and minutes, like `2d 3h 14m`, leaving out the days
+/// and hours while they're 0.
+fn uptime(seconds: u64) -> String {
+    let minutes = seconds / 60 % 60;
+    let hours = seconds / 3600 % 24;
+    let days = seconds / 86400;
+
+    if days > 0 {
+        format!("{days}d {hours}h {minutes}m")
+    } else if hours > 0 {
+        format!("{hours}h {minutes}m")
+    } else {
+        format!("{minutes}m")
+    }
+}
@@ -0,0 +1,208 @@
+//! Memory usage status bar module.
+
+use crate::{
+    block::Block,
+    click::Clicks,
+    modules::Module,
+    template::{Template, Vars},
+    thresholds::Thresholds,
+    units::{self, as_f64},
+};
+use async_stream::stream;
+use futures_util::{stream::LocalBoxStream, Stream, StreamExt};
+use serde::Deserialize;
+use std::{collections::HashMap, fmt, io, time::Duration};
+use tokio::{fs, time::MissedTickBehavior};
+
+/// The file containing the memory statistics.
+const MEMINFO: &str = "/proc/meminfo";
+
+/// Memory usage status bar module, showing the usage of RAM and swap.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Memory {
+    /// How often to read the memory usage, in seconds.
+    pub interval: u64,
+    /// The text to display.
+    ///
+    /// The variables are:
+    ///
+    /// - `icon`
+    /// - `total`, `used`, `free` and `available`, amounts of RAM with a unit, like `1.5 GiB`.
+    ///   Used RAM excludes buffers and caches, like `free` does.
+    /// - `used_percent` and `available_percent`, the used and available RAM as a percentage of
+    ///   the total.
+    /// - `swap_total`, `swap_used` and `swap_free`, amounts of swap with a unit.
+    /// - `swap_used_percent` and `swap_free_percent`, the used and free swap as a percentage of the
+    ///   total, only set when there is swap.
+    pub format: Template,
+    /// The text to display when the bar is shortened, with the same variables as `format`.
+    pub short_format: Template,
+    /// The icon of the block.
+    pub icon: String,
+    /// The color of the block, either a color like `#00ff00` or the name of a color from the
+    /// [`Theme`](crate::theme::Theme).
+    ///
+    /// When this isn't set, the color of the state of the block is used.
+    pub color: Option<String>,
+    /// The thresholds for the state of the block, based on the used RAM as a percentage.
+    pub thresholds: Thresholds,
+    /// Whether to hide the block when there's an error, instead of showing the error.
+    pub hide_errors: bool,
+}
+
+impl Memory {
+    /// Returns a stream of block updates.
+    pub fn stream(self) -> impl Stream<Item = Option<Block>> {
+        stream! {
+            let mut interval = tokio::time::interval(Duration::from_secs(self.interval.max(1)));
+            interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+            loop {
+                interval.tick().await;
+
+                yield match MemInfo::read().await {
+                    Ok(meminfo) => Some(self.block(&meminfo)),
+                    Err(error) => self.error_block(&error),
+                };
+            }
+        }
+    }
+
+    fn block(&self, meminfo: &MemInfo) -> Block {
+        let used_percent = percentage(meminfo.used, meminfo.total);
+        let swap_used = meminfo.swap_total.saturating_sub(meminfo.swap_free);
+
+        let vars = Vars::new()
+            .with("icon", self.icon.as_str())
+            .with("total", units::bytes(as_f64(meminfo.total)))
+            .with("used", units::bytes(as_f64(meminfo.used)))
+            .with("free", units::bytes(as_f64(meminfo.free)))
+            .with("available", units::bytes(as_f64(meminfo.available)))
+            .with("used_percent", used_percent)
+            .with(
+                "available_percent",
+                percentage(meminfo.available, meminfo.total),
+            )
+            .with("swap_total", units::bytes(as_f64(meminfo.swap_total)))
+            .with("swap_used", units::bytes(as_f64(swap_used)))
+            .with("swap_free", units::bytes(as_f64(meminfo.swap_free)))
+            .with_option(
+                "swap_used_percent",
+                (meminfo.swap_total > 0).then(|| percentage(swap_used, meminfo.swap_total)),
+            )
+            .with_option(
+                "swap_free_percent",
+                (meminfo.swap_total > 0).then(|| percentage(meminfo.swap_free, meminfo.swap_total)),
+            );
+
+        Block {
+            text: self.format.render(&vars),
+            short_text: Some(self.short_format.render(&vars)),
+            color: self.color.clone(),
+            percentage: Some(used_percent),
+            state: self.thresholds.state_of_high(used_percent.into()),
+            ..Block::default()
+        }
+    }
+
+    fn error_block(&self, error: &impl fmt::Display) -> Option<Block> {
+        (!self.hide_errors).then(|| Block::error(format!("memory: {error}")))
+    }
+}
+
+impl Module for Memory {
+    fn stream(self: Box<Self>, _clicks: Clicks) -> LocalBoxStream<'static, Option<Block>> {
+        Self::stream(*self).boxed_local()
+    }
+}
+
+impl Default for Memory {
+    fn default() -> Self {
+        Self {
+            interval: 5,
+            format: "{icon} {used_percent}%".parse().expect("valid template"),
+            short_format: "{used_percent}%".parse().expect("valid template"),
+            icon: "\u{f538}".into(),
+            color: None,
+            thresholds: Thresholds::default(),
+            hide_errors: false,
+        }
+    }
+}
+
+/// The memory statistics, in bytes.
+struct MemInfo {
+    total: u64,
+    /// The RAM used by processes, excluding buffers and caches.
+    used: u64,
+    free: u64,
+    /// The RAM that can be used without swapping, as estimated by the kernel.
+    available: u64,
+    swap_total: u64,
+    swap_free: u64,
+}
+
+impl MemInfo {
+    /// Reads the statistics from `/proc/meminfo`, where each line is a name followed by a colon
+    /// and a value, which is in KiB when followed by `kB`.
+    async fn read() -> io::Result<Self> {
+        let meminfo = fs::read_to_string(MEMINFO).await?;
+
+        let values = meminfo
+            .lines()
+            .filter_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                let value = value
+                    .trim()
+                    .trim_end_matches("kB")
+                    .trim()
+                    .parse::<u64>()
+                    .ok()?;
+                Some((name, value * 1024))
+            })
+            .collect::<HashMap<_, _>>();
+
+        let value = |name: &str| {
+            values.get(name).copied().ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("no `{name}` in `{MEMINFO}`"),
+                )
+            })
+        };
+
+        let total = value("MemTotal")?;
+        let free = value("MemFree")?;
+        let buffers = value("Buffers")?;
+        let cached = value("Cached")? + value("SReclaimable").unwrap_or_default();
+
+        // Kernels older than 3.14 don't estimate the available memory, in which case it's the
+        // memory that's free or used by buffers and caches.
+        let available = value("MemAvailable").unwrap_or(free + buffers + cached);
+
+        // This is how `free` calculates the used memory.
+        let used = total.saturating_sub(available);
+
+        Ok(Self {
+            total,
+            used,
+            free,
+            available,
+            swap_total: value("SwapTotal").unwrap_or_default(),
+            swap_free: value("SwapFree").unwrap_or_default(),
+        })
+    }
+}
+
+/// Returns `part` as a percentage of `total`.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn percentage(part: u64, total: u64) -> u8 {
+    if total == 0 {
+        return 0;
+    }
+
+    (as_f64(part) / as_f64(total) * 100.0)
+        .round()
+        .clamp(0.0, 100.0) as u8
+}
@@ -0,0 +1,244 @@
+//! Network interface status bar module.
+
+use crate::{
+    block::{Block, State as BlockState},
+    click::Clicks,
+    modules::Module,
+    net::{self, Addresses},
+    template::{Template, Vars},
+};
+use async_stream::stream;
+use futures_util::{stream::LocalBoxStream, Stream, StreamExt};
+use netlink_sys::{protocols::NETLINK_ROUTE, AsyncSocket, AsyncSocketExt, SocketAddr, TokioSocket};
+use nix::errno::Errno;
+use serde::Deserialize;
+use std::{fmt, io, path::Path};
+use tokio::fs;
+
+/// The directory containing every network interface.
+const NET: &str = "/sys/class/net";
+
+/// The rtnetlink multicast groups of changes to links and their IPv4 and IPv6 addresses.
+const RTMGRP_LINK: u32 = 0x1;
+const RTMGRP_IPV4_IFADDR: u32 = 0x10;
+const RTMGRP_IPV6_IFADDR: u32 = 0x100;
+
+/// Network interface status bar module, for wired links or any other interface.
+#[derive(Deserialize)]
+pub struct Network {
+    /// The name of the interface to watch, for example "eth0".
+    pub interface: String,
+    /// The text to display.
+    ///
+    /// The variables are:
+    ///
+    /// - `icon`
+    /// - `interface`, the name of the interface.
+    /// - `state`, the operational state of the interface, for example `up` or `down`.
+    /// - `speed`, the speed of the link in Mb/s, when it's known.
+    /// - `ipv4` and `ipv6`, the addresses of the interface.
+    #[serde(default = "default_format")]
+    pub format: Template,
+    /// The text to display when the bar is shortened, with the same variables as `format`.
+    #[serde(default = "default_short_format")]
+    pub short_format: Template,
+    /// The icon for each state of the link.
+    #[serde(default)]
+    pub icons: Icons,
+    /// The color for each state of the link.
+    #[serde(default)]
+    pub colors: Colors,
+    /// Whether to hide the block while the link is down.
+    #[serde(default)]
+    pub hide_when_down: bool,
+    /// Whether to hide the block when there's an error, instead of showing the error.
+    #[serde(default)]
+    pub hide_errors: bool,
+}
+
+/// The icon for each state of a link.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Icons {
+    /// The icon when the link is up.
+    pub up: String,
+    /// The icon when the link is down.
+    pub down: String,
+}
+
+impl Default for Icons {
+    fn default() -> Self {
+        Self {
+            up: "\u{f6ff}".into(),
+            down: "\u{f6ff}".into(),
+        }
+    }
+}
+
+/// The color for each state of a link, either a color like `#888888` or the name of a color from
+/// the [`Theme`](crate::theme::Theme).
+///
+/// When a color isn't set, the color of the state of the block is used.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct Colors {
+    /// The color when the link is up.
+    pub up: Option<String>,
+    /// The color when the link is down.
+    pub down: Option<String>,
+}
+
+fn default_format() -> Template {
+    "{icon}[ {ipv4}]".parse().expect("valid template")
+}
+
+fn default_short_format() -> Template {
+    "{icon}".parse().expect("valid template")
+}
+
+impl Network {
+    /// Returns a stream of block updates.
+    ///
+    /// The interface is read again whenever the kernel reports a change to a link or an address.
+    pub fn stream(self) -> impl Stream<Item = Option<Block>> {
+        stream! {
+            let changes = match link_changes() {
+                Ok(changes) => changes,
+                Err(error) => {
+                    yield self.error_block(&error);
+                    return;
+                }
+            };
+
+            loop {
+                yield match Link::read(&self.interface).await {
+                    Ok(link) => self.block(&link),
+                    Err(error) => self.error_block(&error),
+                };
+
+                match changes.recv_from_full().await {
+                    Ok(_) => {}
+                    // The socket's buffer overflowed and some changes were dropped, so the
+                    // interface is read again to catch up.
+                    Err(error) if error.raw_os_error() == Some(Errno::ENOBUFS as i32) => {}
+                    Err(error) => {
+                        yield self.error_block(&error);
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    fn block(&self, link: &Link) -> Option<Block> {
+        if !link.is_up && self.hide_when_down {
+            return None;
+        }
+
+        let (icon, color, state) = if link.is_up {
+            (&self.icons.up, &self.colors.up, None)
+        } else {
+            (&self.icons.down, &self.colors.down, Some(BlockState::Idle))
+        };
+
+        let vars = Vars::new()
+            .with("icon", icon.as_str())
+            .with("interface", self.interface.as_str())
+            .with("state", link.operstate.as_str())
+            .with_option("speed", link.speed)
+            .with_option("ipv4", link.addresses.ipv4.map(|ip| ip.to_string()))
+            .with_option("ipv6", link.addresses.ipv6.map(|ip| ip.to_string()));
+
+        Some(Block {
+            text: self.format.render(&vars),
+            short_text: Some(self.short_format.render(&vars)),
+            color: color.clone(),
+            state,
+            ..Block::default()
+        })
+    }
+
+    fn error_block(&self, error: &impl fmt::Display) -> Option<Block> {
+        (!self.hide_errors).then(|| Block::error(format!("network: {error}")))
+    }
+}
+
+impl Module for Network {
+    fn stream(self: Box<Self>, _clicks: Clicks) -> LocalBoxStream<'static, Option<Block>> {
+        Self::stream(*self).boxed_local()
+    }
+}
+
+/// Opens a socket receiving changes to links and addresses from the kernel.
+///
+/// The messages aren't parsed, as any change is a reason to read the interface again.
+fn link_changes() -> io::Result<TokioSocket> {
+    let mut socket = TokioSocket::new(NETLINK_ROUTE)?;
+    socket.socket_mut().bind(&SocketAddr::new(
+        0,
+        RTMGRP_LINK | RTMGRP_IPV4_IFADDR | RTMGRP_IPV6_IFADDR,
+    ))?;
+    Ok(socket)
+}
+
+/// The state of a network interface.
+struct Link {
+    /// The operational state, as described in
+    /// <https://www.kernel.org/doc/html/latest/networking/operstates.html>.
+    operstate: String,
+    is_up: bool,
+    /// The speed in Mb/s.
+    speed: Option<u32>,
+    addresses: Addresses,
+}
+
+impl Link {
+    async fn read(interface: &str) -> io::Result<Self> {
+        let path = Path::new(NET).join(interface);
+
+        let operstate = match fs::read_to_string(path.join("operstate")).await {
+            Ok(operstate) => operstate.trim().to_owned(),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("interface `{interface}` not found"),
+                ))
+            }
+            Err(error) => return Err(error),
+        };
+
+        // The carrier and speed can't be read while the interface is down.
+        let carrier = read_attribute(&path, "carrier").await;
+
+        // Interfaces without a notion of operational state, such as some virtual interfaces,
+        // report an unknown state, so the carrier is used instead.
+        let is_up = match operstate.as_str() {
+            "up" => true,
+            "unknown" => carrier == Some(1),
+            _ => false,
+        };
+
+        // The speed is -1 when it's unknown.
+        let speed = read_attribute(&path, "speed")
+            .await
+            .and_then(|speed| u32::try_from(speed).ok())
+            .filter(|&speed| speed > 0);
+
+        Ok(Self {
+            operstate,
+            is_up,
+            speed,
+            addresses: net::addresses(interface)?,
+        })
+    }
+}
+
+/// Reads a numeric attribute of an interface, returning `None` if it can't be read.
+async fn read_attribute(path: &Path, attribute: &str) -> Option<i64> {
+    fs::read_to_string(path.join(attribute))
+        .await
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
@@ -0,0 +1,359 @@
+//! Temperature status bar module.
+
+use crate::{
+    block::{Block, State as BlockState},
+    click::Clicks,
+    modules::Module,
+    template::{Template, Vars},
+    thresholds::Thresholds,
+    units::as_f64,
+};
+use async_stream::stream;
+use futures_util::{stream::LocalBoxStream, Stream, StreamExt};
+use serde::Deserialize;
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tokio::{fs, time::MissedTickBehavior};
+
+/// The directory containing every hardware monitoring chip.
+const HWMON: &str = "/sys/class/hwmon";
+
+/// The directory containing every thermal zone.
+const THERMAL: &str = "/sys/class/thermal";
+
+/// Temperature status bar module, showing the temperature of one or more sensors.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Temperature {
+    /// The name of the chip whose sensors to show, like `coretemp` or `k10temp`, or every chip
+    /// when this isn't set.
+    ///
+    /// For hardware monitoring chips, this is the `name` in `/sys/class/hwmon`, and for thermal
+    /// zones, it's the `type` in `/sys/class/thermal`, like `x86_pkg_temp`.
+    pub chip: Option<String>,
+    /// The labels of the sensors to show, like `Package id 0`, or every sensor of the chip when
+    /// this is empty.
+    ///
+    /// Thermal zones have no labels, so they're only shown when this is empty, and only when
+    /// there's no hardware monitoring chip of the same name.
+    pub labels: Vec<String>,
+    /// How to combine the temperatures of several sensors.
+    pub aggregate: Aggregate,
+    /// How often to read the temperature, in seconds.
+    pub interval: u64,
+    /// The text to display.
+    ///
+    /// The variables are:
+    ///
+    /// - `icon`
+    /// - `temperature`, in degrees Celsius.
+    /// - `sensors`, the number of sensors.
+    pub format: Template,
+    /// The text to display when the bar is shortened, with the same variables as `format`.
+    pub short_format: Template,
+    /// The icon of the block.
+    pub icon: String,
+    /// The color of the block, either a color like `#00ff00` or the name of a color from the
+    /// [`Theme`](crate::theme::Theme).
+    ///
+    /// When this isn't set, the color of the state of the block is used.
+    pub color: Option<String>,
+    /// The thresholds for the state of the block, based on the temperature in degrees Celsius.
+    ///
+    /// When no threshold is set, the block is critical when a sensor reaches its critical
+    /// temperature, and warning when it reaches its maximum temperature, as reported by the
+    /// sensor.
+    pub thresholds: Thresholds,
+    /// Whether to hide the block when there's an error, instead of showing the error.
+    pub hide_errors: bool,
+}
+
+/// How to combine the temperatures of several sensors.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Aggregate {
+    /// The highest temperature.
+    #[default]
+    Max,
+    /// The average temperature.
+    Average,
+}
+
+impl Temperature {
+    /// Returns a stream of block updates.
+    ///
+    /// The sensors are looked up again every time, so chips that are loaded later, or renumbered
+    /// after a reboot, are still found.
+    pub fn stream(self) -> impl Stream<Item = Option<Block>> {
+        stream! {
+            let mut interval = tokio::time::interval(Duration::from_secs(self.interval.max(1)));
+            interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+            loop {
+                interval.tick().await;
+
+                yield match self.read().await {
+                    Ok(sensors) => Some(self.block(&sensors)),
+                    Err(error) => self.error_block(&error),
+                };
+            }
+        }
+    }
+
+    /// Reads the sensors to show, returning an error if there are none.
+    async fn read(&self) -> io::Result<Vec<Sensor>> {
+        let mut sensors = Vec::new();
+        let mut hwmon_chips = Vec::new();
+
+        for path in directories(HWMON).await? {
+            let Some(chip) = read_attribute(&path, "name").await else {
+                continue;
+            };
+
+            if self.is_chip(&chip) {
+                sensors.extend(self.read_hwmon(&path).await?);
+            }
+
+            hwmon_chips.push(chip);
+        }
+
+        if self.labels.is_empty() {
+            for path in directories(THERMAL).await? {
+                let is_zone = path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with("thermal_zone"));
+
+                let Some(chip) = read_attribute(&path, "type").await else {
+                    continue;
+                };
+
+                // Some thermal zones, like `acpitz`, are also hardware monitoring chips, which are
+                // only read once.
+                if is_zone && self.is_chip(&chip) && !hwmon_chips.contains(&chip) {
+                    sensors.extend(read_thermal_zone(&path).await);
+                }
+            }
+        }
+
+        if sensors.is_empty() {
+            let error = self.chip.as_ref().map_or_else(
+                || "no sensors found".into(),
+                |chip| format!("no sensors of chip `{chip}` found"),
+            );
+
+            return Err(io::Error::new(io::ErrorKind::NotFound, error));
+        }
+
+        Ok(sensors)
+    }
+
+    /// Returns whether the sensors of a chip are shown.
+    fn is_chip(&self, chip: &str) -> bool {
+        self.chip.as_ref().is_none_or(|name| name == chip)
+    }
+
+    /// Reads the sensors of a hardware monitoring chip with one of `labels`, which have a
+    /// `temp<N>_input` attribute and optionally `temp<N>_label`, `temp<N>_crit` and
+    /// `temp<N>_max` attributes.
+    async fn read_hwmon(&self, path: &Path) -> io::Result<Vec<Sensor>> {
+        let mut sensors = Vec::new();
+        let mut entries = fs::read_dir(path).await?;
+
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name();
+
+            let Some(sensor) = name
+                .to_str()
+                .and_then(|name| name.strip_suffix("_input"))
+                .filter(|sensor| sensor.starts_with("temp"))
+            else {
+                continue;
+            };
+
+            let label = read_attribute(path, &format!("{sensor}_label")).await;
+
+            if !self.labels.is_empty() && !label.is_some_and(|label| self.labels.contains(&label)) {
+                continue;
+            }
+
+            // Some drivers fail to read sensors that are turned off, which are skipped.
+            let Some(temperature) = read_temperature(path, &format!("{sensor}_input")).await else {
+                continue;
+            };
+
+            sensors.push(Sensor {
+                temperature,
+                critical: read_limit(path, &format!("{sensor}_crit")).await,
+                max: read_limit(path, &format!("{sensor}_max")).await,
+            });
+        }
+
+        Ok(sensors)
+    }
+
+    fn block(&self, sensors: &[Sensor]) -> Block {
+        let temperatures = sensors.iter().map(|sensor| sensor.temperature);
+
+        let temperature = match self.aggregate {
+            Aggregate::Max => temperatures.fold(f64::MIN, f64::max),
+            Aggregate::Average => {
+                temperatures.sum::<f64>() / as_f64(u64::try_from(sensors.len()).unwrap_or(u64::MAX))
+            }
+        };
+
+        let state = if self.thresholds == Thresholds::default() {
+            let reached = |limit: fn(&Sensor) -> Option<f64>| {
+                sensors
+                    .iter()
+                    .any(|sensor| limit(sensor).is_some_and(|limit| sensor.temperature >= limit))
+            };
+
+            if reached(|sensor| sensor.critical) {
+                Some(BlockState::Critical)
+            } else if reached(|sensor| sensor.max) {
+                Some(BlockState::Warning)
+            } else {
+                None
+            }
+        } else {
+            self.thresholds.state_of_high(temperature)
+        };
+
+        let vars = Vars::new()
+            .with("icon", self.icon.as_str())
+            .with("temperature", round(temperature))
+            .with("sensors", u64::try_from(sensors.len()).unwrap_or(u64::MAX));
+
+        Block {
+            text: self.format.render(&vars),
+            short_text: Some(self.short_format.render(&vars)),
+            color: self.color.clone(),
+            state,
+            ..Block::default()
+        }
+    }
+
+    fn error_block(&self, error: &impl fmt::Display) -> Option<Block> {
+        (!self.hide_errors).then(|| Block::error(format!("temperature: {error}")))
+    }
+}
+
+impl Module for Temperature {
+    fn stream(self: Box<Self>, _clicks: Clicks) -> LocalBoxStream<'static, Option<Block>> {
+        Self::stream(*self).boxed_local()
+    }
+}
+
+impl Default for Temperature {
+    fn default() -> Self {
+        Self {
+            chip: None,
+            labels: Vec::new(),
+            aggregate: Aggregate::default(),
+            interval: 5,
+            format: "{icon} {temperature}\u{b0}C"
+                .parse()
+                .expect("valid template"),
+            short_format: "{temperature}\u{b0}C".parse().expect("valid template"),
+            icon: "\u{f2c9}".into(),
+            color: None,
+            thresholds: Thresholds::default(),
+            hide_errors: false,
+        }
+    }
+}
+
+/// A temperature sensor, in degrees Celsius.
+struct Sensor {
+    temperature: f64,
+    /// The temperature at which the hardware is in danger, as reported by the sensor.
+    critical: Option<f64>,
+    /// The highest temperature the hardware is meant to run at, as reported by the sensor.
+    max: Option<f64>,
+}
+
+/// Reads a thermal zone, which has a `temp` attribute and trip points with a
+/// `trip_point_<N>_type` and a `trip_point_<N>_temp` attribute.
+///
+/// The `critical` trip point is used as the critical temperature, and the lower of the `hot` and
+/// `passive` trip points, where the hardware starts to be throttled, as the maximum temperature.
+async fn read_thermal_zone(path: &Path) -> Option<Sensor> {
+    let temperature = read_temperature(path, "temp").await?;
+
+    let mut critical = None;
+    let mut max: Option<f64> = None;
+
+    for trip_point in 0.. {
+        let Some(kind) = read_attribute(path, &format!("trip_point_{trip_point}_type")).await
+        else {
+            break;
+        };
+
+        let Some(limit) = read_limit(path, &format!("trip_point_{trip_point}_temp")).await else {
+            continue;
+        };
+
+        match kind.as_str() {
+            "critical" => critical = Some(limit),
+            "hot" | "passive" => max = Some(max.map_or(limit, |max| max.min(limit))),
+            _ => {}
+        }
+    }
+
+    Some(Sensor {
+        temperature,
+        critical,
+        max,
+    })
+}
+
+/// Returns the path of every entry in a class directory, or none if it doesn't exist.
+async fn directories(class: &str) -> io::Result<Vec<PathBuf>> {
+    let mut directories = Vec::new();
+
+    let mut entries = match fs::read_dir(class).await {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(directories),
+        Err(error) => return Err(error),
+    };
+
+    while let Some(entry) = entries.next_entry().await? {
+        directories.push(entry.path());
+    }
+
+    Ok(directories)
+}
+
+/// Reads an attribute, returning `None` if it can't be read.
+async fn read_attribute(path: &Path, attribute: &str) -> Option<String> {
+    fs::read_to_string(path.join(attribute))
+        .await
+        .ok()
+        .map(|value| value.trim().to_owned())
+}
+
+/// Reads a temperature attribute in millidegrees Celsius, returning `None` if it can't be read.
+async fn read_temperature(path: &Path, attribute: &str) -> Option<f64> {
+    read_attribute(path, attribute)
+        .await?
+        .parse::<f64>()
+        .ok()
+        .map(|millidegrees| millidegrees / 1000.0)
+}
+
+/// Reads a temperature limit, returning `None` if it can't be read or isn't above 0, which some
+/// drivers report for limits they don't know.
+async fn read_limit(path: &Path, attribute: &str) -> Option<f64> {
+    read_temperature(path, attribute)
+        .await
+        .filter(|&limit| limit > 0.0)
+}
+
+#[allow(clippy::cast_possible_truncation)]
+const fn round(temperature: f64) -> i64 {
+    temperature.round() as i64
+}
@@ -1,197 +1,375 @@
 //! Wi-Fi status bar module.
 
-use self::interfaces::{DeviceProxy, NetworkProxy, StationProxy};
+mod iwd;
+mod networkmanager;
+
 use crate::{
-    block::Block,
-    dbus::{get_object_path, option_change},
+    block::{Block, State as BlockState},
+    click::Clicks,
+    dbus::{self, OwnerChanges},
+    modules::Module,
+    net::Addresses,
+    template::{Template, Vars},
+    thresholds::Thresholds,
 };
 use async_stream::stream;
-use futures_util::{Stream, StreamExt};
+use futures_util::{stream::LocalBoxStream, Stream, StreamExt};
 use serde::Deserialize;
-use zbus::{
-    fdo::ObjectManagerProxy,
-    zvariant::{ObjectPath, OwnedObjectPath},
-    Connection, PropertyChanged, PropertyStream,
-};
+use std::time::Duration;
+use zbus::Connection;
 
 /// Wi-Fi module config.
 #[derive(Deserialize)]
 pub struct Config {
     /// The name of the interface to watch, for example "wlan0".
     pub interface: String,
+    /// The service managing the device.
+    #[serde(default)]
+    pub backend: Backend,
+    /// The text to display.
+    ///
+    /// The variables are:
+    ///
+    /// - `icon`
+    /// - `network`, the name of the connected network.
+    /// - `signal`, the signal strength as a percentage.
+    /// - `dbm`, the signal strength in dBm, which is only known with iwd.
+    /// - `frequency`, the frequency in MHz.
+    /// - `band`, the frequency band, for example `5 GHz`.
+    /// - `security`, the security of the network, one of `open`, `wep`, `psk` or `8021x`.
+    /// - `ipv4` and `ipv6`, the addresses of the interface.
+    ///
+    /// Apart from `icon`, variables are only set while connected to a network.
+    #[serde(default = "default_format")]
+    pub format: Template,
+    /// The text to display when the bar is shortened, with the same variables as `format`.
+    #[serde(default = "default_short_format")]
+    pub short_format: Template,
+    /// The icon for each state of the device.
+    #[serde(default)]
+    pub icons: Icons,
+    /// The color for each state of the device.
+    #[serde(default)]
+    pub colors: Colors,
+    /// The thresholds for the state of the block, based on the signal strength as a percentage.
+    #[serde(default)]
+    pub thresholds: Thresholds,
+    /// Whether to hide the block while the device, or the Wi-Fi radio with `NetworkManager`, is
+    /// powered off.
+    ///
+    /// The block can't be clicked to power the device back on while it's hidden.
+    #[serde(default)]
+    pub hide_when_off: bool,
+    /// Whether to hide the block when there's an error, instead of showing the error.
+    #[serde(default)]
+    pub hide_errors: bool,
 }
 
 impl Config {
     /// Returns a stream of block updates.
     ///
-    /// # Panics
-    ///
-    /// TODO
-    pub fn stream(self) -> impl Stream<Item = Option<Block>> {
+    /// Clicking the block toggles the power of the device, or the Wi-Fi radio with
+    /// `NetworkManager`. When the service stops or restarts, the device is reconnected to once the
+    /// service is running again, and when the device can't be found, it's looked for again
+    /// whenever the service adds an object.
+    pub fn stream(self, mut clicks: Clicks) -> impl Stream<Item = Option<Block>> {
         stream! {
-            let connection = Connection::system().await.unwrap();
-            let mut wifi = Wifi::new(&connection, self).await.unwrap();
+            let (connection, backend, mut owner_changes) = match connect(self.backend).await {
+                Ok(connection) => connection,
+                Err(error) => {
+                    yield self.error_block(&error);
+                    return;
+                }
+            };
+
+            let (service, object_manager) = backend.service();
+
             loop {
-                yield wifi.block();
-                wifi.wait_for_update().await;
+                // Objects are watched before looking for the device, so one added in between
+                // isn't missed, for example when a USB device is plugged in.
+                let mut objects_added = dbus::objects_added(&connection, service, object_manager)
+                    .await
+                    .ok();
+
+                let error = match Device::new(&connection, backend, &self).await {
+                    Ok(mut device) => {
+                        // The objects added are no longer needed, and would otherwise pile up.
+                        objects_added = None;
+
+                        loop {
+                            yield self.block(device.state(), device.details());
+
+                            let update = device.wait_for_update(&mut clicks, &mut owner_changes);
+
+                            if let Err(error) = update.await {
+                                break error;
+                            }
+                        }
+                    }
+                    Err(error) => error,
+                };
+
+                match error {
+                    dbus::Error::Restarted => {}
+                    dbus::Error::Stopped => {
+                        yield self.error_block(&error);
+
+                        if !dbus::wait_for_owner(&mut owner_changes).await {
+                            return;
+                        }
+                    }
+                    error => {
+                        yield self.error_block(&error);
+
+                        if !dbus::wait_for_object(objects_added.as_mut(), &mut owner_changes)
+                            .await
+                        {
+                            return;
+                        }
+                    }
+                }
             }
         }
     }
+
+    fn block(&self, state: &State, details: &Details) -> Option<Block> {
+        let network = match state {
+            State::PoweredOff if self.hide_when_off => return None,
+            State::PoweredOff | State::Disconnected | State::Connected => None,
+            State::ConnectedTo(network) => Some(network.as_str()),
+        };
+
+        let (icon, color, state, details) = match state {
+            State::PoweredOff => (
+                &self.icons.off,
+                &self.colors.off,
+                Some(BlockState::Idle),
+                &Details::default(),
+            ),
+            State::Disconnected => (
+                &self.icons.disconnected,
+                &self.colors.disconnected,
+                Some(BlockState::Idle),
+                &Details::default(),
+            ),
+            State::Connected | State::ConnectedTo(_) => {
+                let state = details
+                    .signal
+                    .and_then(|signal| self.thresholds.state_of_low(signal.into()));
+
+                (
+                    &self.icons.connected,
+                    &self.colors.connected,
+                    state,
+                    details,
+                )
+            }
+        };
+
+        let vars = Vars::new()
+            .with("icon", icon.as_str())
+            .with_option("network", network)
+            .with_option("signal", details.signal)
+            .with_option("dbm", details.dbm)
+            .with_option("frequency", details.frequency)
+            .with_option("band", details.frequency.and_then(band))
+            .with_option("security", details.security.as_deref())
+            .with_option("ipv4", details.addresses.ipv4.map(|ip| ip.to_string()))
+            .with_option("ipv6", details.addresses.ipv6.map(|ip| ip.to_string()));
+
+        Some(Block {
+            text: self.format.render(&vars),
+            short_text: Some(self.short_format.render(&vars)),
+            color: color.clone(),
+            tooltip: network.map(ToOwned::to_owned),
+            state,
+            ..Block::default()
+        })
+    }
+
+    fn error_block(&self, error: &dbus::Error) -> Option<Block> {
+        (!self.hide_errors).then(|| Block::error(format!("wifi: {error}")))
+    }
 }
 
-/// Wi-Fi status bar module.
-pub struct Wifi<'a> {
-    connection: &'a Connection,
-    device_path: ObjectPath<'a>,
-    powered_changes: PropertyStream<'a, bool>,
-    connected_network_changes: Option<PropertyStream<'a, OwnedObjectPath>>,
-    connected_network_name_changes: Option<PropertyStream<'a, String>>,
-    state: State,
+impl Module for Config {
+    fn stream(self: Box<Self>, clicks: Clicks) -> LocalBoxStream<'static, Option<Block>> {
+        Self::stream(*self, clicks).boxed_local()
+    }
 }
 
-impl<'a> Wifi<'a> {
-    /// Creates a new instance of [`Wifi`].
-    ///
-    /// # Errors
-    ///
-    /// TODO
-    ///
-    /// # Panics
-    ///
-    /// TODO
-    pub async fn new(connection: &'a Connection, config: Config) -> zbus::Result<Wifi<'a>> {
-        let object_manager = ObjectManagerProxy::builder(connection)
-            .destination("net.connman.iwd")
-            .expect("valid bus name")
-            .path("/")
-            .expect("valid path")
-            .build()
-            .await?;
-
-        let device_path = get_object_path(
-            &object_manager.get_managed_objects().await?,
-            "net.connman.iwd.Device",
-            "Name",
-            config.interface.as_str(),
-        )
-        .unwrap()
-        .to_owned();
-
-        let device = DeviceProxy::builder(connection)
-            .path(&device_path)
-            .expect("valid path")
-            .build()
-            .await?;
-
-        Ok(Self {
-            connection,
-            device_path,
-            powered_changes: device.receive_powered_changed().await,
-            connected_network_changes: None,
-            connected_network_name_changes: None,
-            state: State::PoweredOff,
-        })
+/// The service managing a Wi-Fi device.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+    /// iwd when it's running, otherwise `NetworkManager` when it's running, otherwise iwd.
+    #[default]
+    Auto,
+    /// iwd.
+    Iwd,
+    /// `NetworkManager`.
+    NetworkManager,
+}
+
+impl Backend {
+    /// Returns the name of the service and the path of its object manager.
+    const fn service(self) -> (&'static str, &'static str) {
+        match self {
+            Self::Auto | Self::Iwd => (iwd::SERVICE, iwd::OBJECT_MANAGER),
+            Self::NetworkManager => (networkmanager::SERVICE, networkmanager::OBJECT_MANAGER),
+        }
     }
+}
 
-    /// Returns a stream of block updates.
-    pub fn stream(mut self) -> impl Stream<Item = Option<Block>> + 'a {
-        stream! {
-            loop {
-                yield self.block();
-                self.wait_for_update().await;
-            }
+/// The icon for each state of a Wi-Fi device.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Icons {
+    /// The icon when connected to a network.
+    pub connected: String,
+    /// The icon when not connected to a network.
+    pub disconnected: String,
+    /// The icon when the device is powered off.
+    pub off: String,
+}
+
+impl Default for Icons {
+    fn default() -> Self {
+        Self {
+            connected: "\u{f1eb}".into(),
+            disconnected: "\u{f1eb}".into(),
+            off: "\u{f1eb}".into(),
         }
     }
+}
 
-    async fn wait_for_update(&mut self) {
-        tokio::select! {
-            Some(change) = self.powered_changes.next() => {
-                self.handle_powered_change(change).await;
-            },
+/// The color for each state of a Wi-Fi device, either a color like `#888888` or the name of a
+/// color from the [`Theme`](crate::theme::Theme).
+///
+/// When a color isn't set, the color of the state of the block is used.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct Colors {
+    /// The color when connected to a network.
+    pub connected: Option<String>,
+    /// The color when not connected to a network.
+    pub disconnected: Option<String>,
+    /// The color when the device is powered off.
+    pub off: Option<String>,
+}
 
-            Some(Some(change)) = option_change(self.connected_network_changes.as_mut()) => {
-                self.handle_connected_network_change(change).await;
-            },
+fn default_format() -> Template {
+    "{icon}[ {network}]".parse().expect("valid template")
+}
 
-            Some(Some(change)) = option_change(self.connected_network_name_changes.as_mut()) => {
-                self.handle_connected_network_name_change(change).await;
-            },
+fn default_short_format() -> Template {
+    "{icon}".parse().expect("valid template")
+}
+
+/// Connects to the system bus, and starts watching for changes to the owner of the service
+/// managing the device.
+///
+/// Returns the backend to use, which is never [`Backend::Auto`].
+async fn connect(backend: Backend) -> Result<(Connection, Backend, OwnerChanges), dbus::Error> {
+    let connection = Connection::system().await?;
+
+    let backend = match backend {
+        Backend::Auto => {
+            if !dbus::has_owner(&connection, iwd::SERVICE).await?
+                && dbus::has_owner(&connection, networkmanager::SERVICE).await?
+            {
+                Backend::NetworkManager
+            } else {
+                Backend::Iwd
+            }
         }
+        backend => backend,
+    };
+
+    let (service, _) = backend.service();
+    let owner_changes = dbus::owner_changes(&connection, service).await?;
+
+    Ok((connection, backend, owner_changes))
+}
+
+/// How often to update the details of the connected network that aren't reported as they change.
+const DETAILS_PERIOD: Duration = Duration::from_secs(10);
+
+/// A Wi-Fi device managed by one of the backends.
+enum Device<'a> {
+    Iwd(iwd::Device<'a>),
+    NetworkManager(networkmanager::Device<'a>),
+}
+
+impl<'a> Device<'a> {
+    async fn new(
+        connection: &'a Connection,
+        backend: Backend,
+        config: &Config,
+    ) -> Result<Device<'a>, dbus::Error> {
+        Ok(match backend {
+            Backend::Auto | Backend::Iwd => Self::Iwd(iwd::Device::new(connection, config).await?),
+            Backend::NetworkManager => {
+                Self::NetworkManager(networkmanager::Device::new(connection, config).await?)
+            }
+        })
     }
 
-    fn block(&self) -> Option<Block> {
-        match &self.state {
-            State::PoweredOff => None,
-            State::Disconnected => Some(Block {
-                text: "".into(),
-                short_text: Some("".into()),
-                color: Some("#888888".into()),
-            }),
-            State::Connected => Some(Block {
-                text: "".into(),
-                short_text: Some("".into()),
-                color: None,
-            }),
-            State::ConnectedTo(network_name) => Some(Block {
-                text: format!(" {network_name}"),
-                short_text: Some("".into()),
-                color: None,
-            }),
+    const fn state(&self) -> &State {
+        match self {
+            Self::Iwd(device) => device.state(),
+            Self::NetworkManager(device) => device.state(),
         }
     }
 
-    async fn handle_powered_change(&mut self, change: PropertyChanged<'a, bool>) {
-        if change.get().await.unwrap_or_default() {
-            self.connected_network_changes = Some(
-                StationProxy::builder(self.connection)
-                    .path(&self.device_path)
-                    .expect("valid path")
-                    .build()
-                    .await
-                    .unwrap()
-                    .receive_connected_network_changed()
-                    .await,
-            );
-
-            self.state = State::Disconnected;
-        } else {
-            self.state = State::PoweredOff;
-            self.connected_network_changes = None;
-            self.connected_network_name_changes = None;
+    const fn details(&self) -> &Details {
+        match self {
+            Self::Iwd(device) => device.details(),
+            Self::NetworkManager(device) => device.details(),
         }
     }
 
-    async fn handle_connected_network_change(
+    async fn wait_for_update(
         &mut self,
-        change: PropertyChanged<'a, OwnedObjectPath>,
-    ) {
-        if let Ok(connected_network) = change.get().await {
-            self.connected_network_name_changes = Some(
-                NetworkProxy::builder(self.connection)
-                    .path(connected_network)
-                    .expect("valid path")
-                    .build()
-                    .await
-                    .unwrap()
-                    .receive_name_changed()
-                    .await,
-            );
-            self.state = State::Connected;
-        } else {
-            self.state = State::Disconnected;
-            self.connected_network_name_changes = None;
+        clicks: &mut Clicks,
+        owner_changes: &mut OwnerChanges,
+    ) -> Result<(), dbus::Error> {
+        match self {
+            Self::Iwd(device) => device.wait_for_update(clicks, owner_changes).await,
+            Self::NetworkManager(device) => device.wait_for_update(clicks, owner_changes).await,
         }
     }
+}
 
-    async fn handle_connected_network_name_change(&mut self, change: PropertyChanged<'a, String>) {
-        if let Ok(connected_network_name) = change.get().await {
-            self.state = State::ConnectedTo(connected_network_name);
-        } else {
-            self.state = State::Connected;
-        }
+/// Details of the connected network.
+#[derive(Debug, Default)]
+struct Details {
+    /// The signal strength as a percentage.
+    signal: Option<u8>,
+    /// The signal strength in dBm.
+    dbm: Option<i16>,
+    /// The frequency in MHz.
+    frequency: Option<u32>,
+    security: Option<String>,
+    addresses: Addresses,
+}
+
+/// Converts a signal strength in dBm to a percentage, where -50 dBm or more is 100%.
+fn signal_quality(dbm: i16) -> u8 {
+    u8::try_from((2 * (i32::from(dbm) + 100)).clamp(0, 100)).unwrap_or_default()
+}
+
+/// Returns the band of a frequency in MHz.
+const fn band(frequency: u32) -> Option<&'static str> {
+    match frequency {
+        2400..=2500 => Some("2.4 GHz"),
+        4900..=5899 => Some("5 GHz"),
+        5900..=7125 => Some("6 GHz"),
+        _ => None,
     }
 }
 
+/// The state of a Wi-Fi device, which each backend goes through in the same way.
 #[derive(Debug)]
 enum State {
     PoweredOff,
@@ -199,34 +377,3 @@ enum State {
     Connected,
     ConnectedTo(String),
 }
-
-mod interfaces {
-    use zbus::zvariant::OwnedObjectPath;
-
-    #[zbus::dbus_proxy(
-        interface = "net.connman.iwd.Device",
-        default_service = "net.connman.iwd"
-    )]
-    trait Device {
-        #[dbus_proxy(property)]
-        fn powered(&self) -> zbus::Result<bool>;
-    }
-
-    #[zbus::dbus_proxy(
-        interface = "net.connman.iwd.Station",
-        default_service = "net.connman.iwd"
-    )]
-    trait Station {
-        #[dbus_proxy(property)]
-        fn connected_network(&self) -> zbus::Result<OwnedObjectPath>;
-    }
-
-    #[zbus::dbus_proxy(
-        interface = "net.connman.iwd.Network",
-        default_service = "net.connman.iwd"
-    )]
-    trait Network {
-        #[dbus_proxy(property)]
-        fn name(&self) -> zbus::Result<String>;
-    }
-}
@@ -0,0 +1,295 @@
+//! Watching a Wi-Fi device with iwd.
+
+use self::interfaces::{DeviceProxy, NetworkProxy, StationDiagnosticProxy, StationProxy};
+use super::{signal_quality, Config, Details, State, DETAILS_PERIOD};
+use crate::{
+    click::{Button, Click, Clicks},
+    dbus::{self, get_object_path, option_change, OwnerChanges},
+    net,
+};
+use futures_util::StreamExt;
+use tokio::time::{Interval, MissedTickBehavior};
+use zbus::{
+    zvariant::{ObjectPath, OwnedObjectPath},
+    Connection, PropertyChanged, PropertyStream,
+};
+
+/// The name of the iwd service.
+pub const SERVICE: &str = "net.connman.iwd";
+
+/// The path of the object manager of iwd.
+pub const OBJECT_MANAGER: &str = "/";
+
+/// A Wi-Fi device managed by iwd.
+pub struct Device<'a> {
+    connection: &'a Connection,
+    interface: String,
+    path: ObjectPath<'a>,
+    powered_changes: PropertyStream<'a, bool>,
+    connected_network_changes: Option<PropertyStream<'a, OwnedObjectPath>>,
+    connected_network_name_changes: Option<PropertyStream<'a, String>>,
+    connected_network: Option<OwnedObjectPath>,
+    details: Details,
+    details_interval: Interval,
+    state: State,
+}
+
+impl<'a> Device<'a> {
+    /// Creates a new instance of [`Device`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when communication with iwd fails, or the interface doesn't exist.
+    pub async fn new(
+        connection: &'a Connection,
+        config: &Config,
+    ) -> Result<Device<'a>, dbus::Error> {
+        let object_manager = dbus::object_manager(connection, SERVICE).await?;
+
+        let path = get_object_path(
+            &object_manager.get_managed_objects().await?,
+            "net.connman.iwd.Device",
+            "Name",
+            config.interface.as_str(),
+        )
+        .ok_or_else(|| dbus::Error::NotFound(format!("interface {}", config.interface)))?
+        .to_owned();
+
+        let device = DeviceProxy::builder(connection)
+            .path(&path)?
+            .build()
+            .await?;
+
+        let mut details_interval = tokio::time::interval(DETAILS_PERIOD);
+        details_interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        Ok(Self {
+            connection,
+            interface: config.interface.clone(),
+            path,
+            powered_changes: device.receive_powered_changed().await,
+            connected_network_changes: None,
+            connected_network_name_changes: None,
+            connected_network: None,
+            details: Details::default(),
+            details_interval,
+            state: State::PoweredOff,
+        })
+    }
+
+    pub const fn state(&self) -> &State {
+        &self.state
+    }
+
+    pub const fn details(&self) -> &Details {
+        &self.details
+    }
+
+    pub async fn wait_for_update(
+        &mut self,
+        clicks: &mut Clicks,
+        owner_changes: &mut OwnerChanges,
+    ) -> Result<(), dbus::Error> {
+        tokio::select! {
+            Some(has_owner) = owner_changes.next() => {
+                return Err(dbus::owner_change_error(has_owner));
+            },
+
+            Some(change) = self.powered_changes.next() => {
+                self.handle_powered_change(change).await?;
+            },
+
+            Some(Some(change)) = option_change(self.connected_network_changes.as_mut()) => {
+                self.handle_connected_network_change(change).await?;
+            },
+
+            Some(Some(change)) = option_change(self.connected_network_name_changes.as_mut()) => {
+                self.handle_connected_network_name_change(change).await;
+            },
+
+            _ = self.details_interval.tick(), if self.connected_network.is_some() => {
+                self.update_details().await;
+            },
+
+            Some(click) = clicks.recv() => {
+                self.handle_click(click).await;
+            },
+        }
+
+        Ok(())
+    }
+
+    async fn handle_click(&self, click: Click) {
+        if click.button != Button::Left {
+            return;
+        }
+
+        let powered = !matches!(self.state, State::PoweredOff);
+
+        if let Ok(device) = DeviceProxy::builder(self.connection)
+            .path(&self.path)
+            .expect("valid path")
+            .build()
+            .await
+        {
+            // The power change is reported back through `powered_changes`, so a failure here
+            // simply leaves the block as it is.
+            let _ = device.set_powered(!powered).await;
+        }
+    }
+
+    async fn handle_powered_change(
+        &mut self,
+        change: PropertyChanged<'a, bool>,
+    ) -> zbus::Result<()> {
+        if change.get().await.unwrap_or_default() {
+            self.connected_network_changes = Some(
+                StationProxy::builder(self.connection)
+                    .path(&self.path)?
+                    .build()
+                    .await?
+                    .receive_connected_network_changed()
+                    .await,
+            );
+
+            self.state = State::Disconnected;
+        } else {
+            self.state = State::PoweredOff;
+            self.connected_network_changes = None;
+            self.connected_network_name_changes = None;
+            self.connected_network = None;
+            self.details = Details::default();
+        }
+
+        Ok(())
+    }
+
+    async fn handle_connected_network_change(
+        &mut self,
+        change: PropertyChanged<'a, OwnedObjectPath>,
+    ) -> zbus::Result<()> {
+        if let Ok(connected_network) = change.get().await {
+            let network = NetworkProxy::builder(self.connection)
+                .path(connected_network.clone())?
+                .build()
+                .await?;
+
+            self.connected_network_name_changes = Some(network.receive_name_changed().await);
+            self.connected_network = Some(connected_network);
+            self.details.security = network.network_type().await.ok();
+            self.update_details().await;
+            self.state = State::Connected;
+        } else {
+            self.state = State::Disconnected;
+            self.connected_network_name_changes = None;
+            self.connected_network = None;
+            self.details = Details::default();
+        }
+
+        Ok(())
+    }
+
+    /// Updates the details of the connected network that aren't reported as property changes.
+    ///
+    /// Details that can't be read are left unset, as they're not essential.
+    async fn update_details(&mut self) {
+        let Some(connected_network) = &self.connected_network else {
+            return;
+        };
+
+        if let Ok(station) = StationProxy::builder(self.connection)
+            .path(&self.path)
+            .expect("valid path")
+            .build()
+            .await
+        {
+            // Signal strengths are reported in 100 * dBm.
+            self.details.dbm = station
+                .get_ordered_networks()
+                .await
+                .ok()
+                .and_then(|networks| {
+                    networks
+                        .into_iter()
+                        .find(|(network, _)| network == connected_network)
+                })
+                .map(|(_, signal)| signal / 100);
+
+            self.details.signal = self.details.dbm.map(signal_quality);
+        }
+
+        if let Ok(diagnostic) = StationDiagnosticProxy::builder(self.connection)
+            .path(&self.path)
+            .expect("valid path")
+            .build()
+            .await
+        {
+            self.details.frequency =
+                diagnostic
+                    .get_diagnostics()
+                    .await
+                    .ok()
+                    .and_then(|diagnostics| {
+                        diagnostics.get("Frequency")?.downcast_ref::<u32>().copied()
+                    });
+        }
+
+        self.details.addresses = net::addresses(&self.interface).unwrap_or_default();
+    }
+
+    async fn handle_connected_network_name_change(&mut self, change: PropertyChanged<'a, String>) {
+        if let Ok(connected_network_name) = change.get().await {
+            self.state = State::ConnectedTo(connected_network_name);
+        } else {
+            self.state = State::Connected;
+        }
+    }
+}
+
+mod interfaces {
+    use std::collections::HashMap;
+    use zbus::zvariant::{OwnedObjectPath, OwnedValue};
+
+    #[zbus::dbus_proxy(
+        interface = "net.connman.iwd.Device",
+        default_service = "net.connman.iwd"
+    )]
+    trait Device {
+        #[dbus_proxy(property)]
+        fn powered(&self) -> zbus::Result<bool>;
+
+        #[dbus_proxy(property)]
+        fn set_powered(&self, value: bool) -> zbus::Result<()>;
+    }
+
+    #[zbus::dbus_proxy(
+        interface = "net.connman.iwd.Station",
+        default_service = "net.connman.iwd"
+    )]
+    trait Station {
+        #[dbus_proxy(property)]
+        fn connected_network(&self) -> zbus::Result<OwnedObjectPath>;
+
+        fn get_ordered_networks(&self) -> zbus::Result<Vec<(OwnedObjectPath, i16)>>;
+    }
+
+    #[zbus::dbus_proxy(
+        interface = "net.connman.iwd.StationDiagnostic",
+        default_service = "net.connman.iwd"
+    )]
+    trait StationDiagnostic {
+        fn get_diagnostics(&self) -> zbus::Result<HashMap<String, OwnedValue>>;
+    }
+
+    #[zbus::dbus_proxy(
+        interface = "net.connman.iwd.Network",
+        default_service = "net.connman.iwd"
+    )]
+    trait Network {
+        #[dbus_proxy(property)]
+        fn name(&self) -> zbus::Result<String>;
+
+        #[dbus_proxy(property, name = "Type")]
+        fn network_type(&self) -> zbus::Result<String>;
+    }
+}
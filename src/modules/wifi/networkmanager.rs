@@ -0,0 +1,324 @@
+//! Watching a Wi-Fi device with `NetworkManager`.
+
+use self::interfaces::{AccessPointProxy, DeviceProxy, NetworkManagerProxy, WirelessProxy};
+use super::{Config, Details, State, DETAILS_PERIOD};
+use crate::{
+    click::{Button, Click, Clicks},
+    dbus::{self, option_change, OwnerChanges},
+    net,
+};
+use futures_util::StreamExt;
+use tokio::time::{Interval, MissedTickBehavior};
+use zbus::{zvariant::OwnedObjectPath, Connection, PropertyChanged, PropertyStream};
+
+/// The name of the `NetworkManager` service.
+pub const SERVICE: &str = "org.freedesktop.NetworkManager";
+
+/// The path of the object manager of `NetworkManager`, which reports devices as they're added.
+pub const OBJECT_MANAGER: &str = "/org/freedesktop";
+
+/// The type of Wi-Fi devices.
+///
+/// See <https://networkmanager.dev/docs/api/latest/nm-dbus-types.html#NMDeviceType>.
+const DEVICE_TYPE_WIFI: u32 = 2;
+
+/// The state of a device with an active connection.
+///
+/// See <https://networkmanager.dev/docs/api/latest/nm-dbus-types.html#NMDeviceState>.
+const DEVICE_STATE_ACTIVATED: u32 = 100;
+
+/// A Wi-Fi device managed by `NetworkManager`.
+///
+/// `NetworkManager` reports the radio, the state of the device and the access point separately, so
+/// each is tracked as it changes and combined into a [`State`].
+pub struct Device<'a> {
+    connection: &'a Connection,
+    interface: String,
+    wireless_enabled_changes: PropertyStream<'a, bool>,
+    state_changes: PropertyStream<'a, u32>,
+    access_point_changes: PropertyStream<'a, OwnedObjectPath>,
+    ssid_changes: Option<PropertyStream<'a, Vec<u8>>>,
+    strength_changes: Option<PropertyStream<'a, u8>>,
+    wireless_enabled: bool,
+    is_activated: bool,
+    access_point: Option<OwnedObjectPath>,
+    ssid: Option<String>,
+    details: Details,
+    details_interval: Interval,
+    state: State,
+}
+
+impl<'a> Device<'a> {
+    /// Creates a new instance of [`Device`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when communication with `NetworkManager` fails, or the interface doesn't
+    /// exist or isn't a Wi-Fi device.
+    pub async fn new(
+        connection: &'a Connection,
+        config: &Config,
+    ) -> Result<Device<'a>, dbus::Error> {
+        let not_found = || dbus::Error::NotFound(format!("interface {}", config.interface));
+
+        // Each stream is created right after its proxy, so that it receives the initial value of
+        // the property once it's read.
+        let network_manager = NetworkManagerProxy::new(connection).await?;
+        let wireless_enabled_changes = network_manager.receive_wireless_enabled_changed().await;
+
+        let device_path = match network_manager
+            .get_device_by_ip_iface(&config.interface)
+            .await
+        {
+            Ok(device_path) => device_path,
+            Err(zbus::Error::MethodError(..)) => return Err(not_found()),
+            Err(error) => return Err(error.into()),
+        };
+
+        let device = DeviceProxy::builder(connection)
+            .path(device_path.clone())?
+            .build()
+            .await?;
+        let state_changes = device.receive_state_changed().await;
+
+        if device.device_type().await? != DEVICE_TYPE_WIFI {
+            return Err(not_found());
+        }
+
+        let wireless = WirelessProxy::builder(connection)
+            .path(device_path)?
+            .build()
+            .await?;
+        let access_point_changes = wireless.receive_active_access_point_changed().await;
+
+        let mut details_interval = tokio::time::interval(DETAILS_PERIOD);
+        details_interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        Ok(Self {
+            connection,
+            interface: config.interface.clone(),
+            wireless_enabled_changes,
+            state_changes,
+            access_point_changes,
+            ssid_changes: None,
+            strength_changes: None,
+            wireless_enabled: false,
+            is_activated: false,
+            access_point: None,
+            ssid: None,
+            details: Details::default(),
+            details_interval,
+            state: State::PoweredOff,
+        })
+    }
+
+    pub const fn state(&self) -> &State {
+        &self.state
+    }
+
+    pub const fn details(&self) -> &Details {
+        &self.details
+    }
+
+    pub async fn wait_for_update(
+        &mut self,
+        clicks: &mut Clicks,
+        owner_changes: &mut OwnerChanges,
+    ) -> Result<(), dbus::Error> {
+        tokio::select! {
+            Some(has_owner) = owner_changes.next() => {
+                return Err(dbus::owner_change_error(has_owner));
+            },
+
+            Some(change) = self.wireless_enabled_changes.next() => {
+                self.wireless_enabled = change.get().await.unwrap_or_default();
+            },
+
+            Some(change) = self.state_changes.next() => {
+                self.is_activated = change.get().await.ok() == Some(DEVICE_STATE_ACTIVATED);
+                self.update_addresses();
+            },
+
+            Some(change) = self.access_point_changes.next() => {
+                self.handle_access_point_change(change).await?;
+            },
+
+            Some(Some(change)) = option_change(self.ssid_changes.as_mut()) => {
+                self.ssid = change
+                    .get()
+                    .await
+                    .ok()
+                    .map(|ssid| String::from_utf8_lossy(&ssid).into_owned());
+            },
+
+            Some(Some(change)) = option_change(self.strength_changes.as_mut()) => {
+                self.details.signal = change.get().await.ok();
+            },
+
+            _ = self.details_interval.tick(), if self.access_point.is_some() => {
+                self.update_addresses();
+            },
+
+            Some(click) = clicks.recv() => {
+                self.handle_click(click).await;
+            },
+        }
+
+        self.update_state();
+
+        Ok(())
+    }
+
+    /// Combines the radio, the state of the device and the access point into a [`State`].
+    fn update_state(&mut self) {
+        self.state = if !self.wireless_enabled {
+            State::PoweredOff
+        } else if !self.is_activated || self.access_point.is_none() {
+            State::Disconnected
+        } else {
+            self.ssid
+                .clone()
+                .map_or(State::Connected, State::ConnectedTo)
+        };
+    }
+
+    async fn handle_click(&self, click: Click) {
+        if click.button != Button::Left {
+            return;
+        }
+
+        if let Ok(network_manager) = NetworkManagerProxy::new(self.connection).await {
+            // The change is reported back through `wireless_enabled_changes`, so a failure here
+            // simply leaves the block as it is.
+            let _ = network_manager
+                .set_wireless_enabled(!self.wireless_enabled)
+                .await;
+        }
+    }
+
+    async fn handle_access_point_change(
+        &mut self,
+        change: PropertyChanged<'a, OwnedObjectPath>,
+    ) -> zbus::Result<()> {
+        // The path is `/` when there's no access point.
+        let access_point = change
+            .get()
+            .await
+            .ok()
+            .filter(|access_point| access_point.as_str() != "/");
+
+        self.ssid_changes = None;
+        self.strength_changes = None;
+        self.ssid = None;
+        self.details = Details::default();
+
+        if let Some(access_point) = &access_point {
+            let access_point = AccessPointProxy::builder(self.connection)
+                .path(access_point.clone())?
+                .build()
+                .await?;
+
+            self.ssid_changes = Some(access_point.receive_ssid_changed().await);
+            self.strength_changes = Some(access_point.receive_strength_changed().await);
+            self.details.frequency = access_point.frequency().await.ok();
+            self.details.security = security(&access_point).await.ok().map(Into::into);
+            self.update_addresses();
+        }
+
+        self.access_point = access_point;
+
+        Ok(())
+    }
+
+    fn update_addresses(&mut self) {
+        self.details.addresses = net::addresses(&self.interface).unwrap_or_default();
+    }
+}
+
+/// Returns the security of an access point, using the same names as iwd.
+///
+/// See <https://networkmanager.dev/docs/api/latest/nm-dbus-types.html#NM80211ApSecurityFlags>.
+async fn security(access_point: &AccessPointProxy<'_>) -> zbus::Result<&'static str> {
+    const PRIVACY: u32 = 0x1;
+    const KEY_MGMT_PSK: u32 = 0x100;
+    const KEY_MGMT_802_1X: u32 = 0x200;
+    const KEY_MGMT_SAE: u32 = 0x400;
+
+    let flags = access_point.wpa_flags().await? | access_point.rsn_flags().await?;
+
+    let security = if flags & KEY_MGMT_802_1X != 0 {
+        "8021x"
+    } else if flags & (KEY_MGMT_PSK | KEY_MGMT_SAE) != 0 {
+        "psk"
+    } else if access_point.flags().await? & PRIVACY != 0 {
+        "wep"
+    } else {
+        "open"
+    };
+
+    Ok(security)
+}
+
+mod interfaces {
+    use zbus::zvariant::OwnedObjectPath;
+
+    #[zbus::dbus_proxy(
+        interface = "org.freedesktop.NetworkManager",
+        default_service = "org.freedesktop.NetworkManager",
+        default_path = "/org/freedesktop/NetworkManager"
+    )]
+    trait NetworkManager {
+        fn get_device_by_ip_iface(&self, iface: &str) -> zbus::Result<OwnedObjectPath>;
+
+        #[dbus_proxy(property)]
+        fn wireless_enabled(&self) -> zbus::Result<bool>;
+
+        #[dbus_proxy(property)]
+        fn set_wireless_enabled(&self, value: bool) -> zbus::Result<()>;
+    }
+
+    #[zbus::dbus_proxy(
+        interface = "org.freedesktop.NetworkManager.Device",
+        default_service = "org.freedesktop.NetworkManager"
+    )]
+    trait Device {
+        #[dbus_proxy(property)]
+        fn device_type(&self) -> zbus::Result<u32>;
+
+        #[dbus_proxy(property)]
+        fn state(&self) -> zbus::Result<u32>;
+    }
+
+    #[zbus::dbus_proxy(
+        interface = "org.freedesktop.NetworkManager.Device.Wireless",
+        default_service = "org.freedesktop.NetworkManager"
+    )]
+    trait Wireless {
+        #[dbus_proxy(property)]
+        fn active_access_point(&self) -> zbus::Result<OwnedObjectPath>;
+    }
+
+    #[zbus::dbus_proxy(
+        interface = "org.freedesktop.NetworkManager.AccessPoint",
+        default_service = "org.freedesktop.NetworkManager"
+    )]
+    trait AccessPoint {
+        #[dbus_proxy(property)]
+        fn ssid(&self) -> zbus::Result<Vec<u8>>;
+
+        #[dbus_proxy(property)]
+        fn strength(&self) -> zbus::Result<u8>;
+
+        #[dbus_proxy(property)]
+        fn frequency(&self) -> zbus::Result<u32>;
+
+        #[dbus_proxy(property)]
+        fn flags(&self) -> zbus::Result<u32>;
+
+        #[dbus_proxy(property)]
+        fn wpa_flags(&self) -> zbus::Result<u32>;
+
+        #[dbus_proxy(property)]
+        fn rsn_flags(&self) -> zbus::Result<u32>;
+    }
+}
@@ -0,0 +1,55 @@
+//! Utilities for working with network interfaces.
+
+use nix::ifaddrs::getifaddrs;
+use std::{
+    io,
+    net::{Ipv4Addr, Ipv6Addr},
+};
+
+/// The addresses of a network interface.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Addresses {
+    /// The first IPv4 address of the interface.
+    pub ipv4: Option<Ipv4Addr>,
+    /// The first IPv6 address of the interface, preferring addresses that aren't link-local.
+    pub ipv6: Option<Ipv6Addr>,
+}
+
+/// Returns the addresses of a network interface.
+///
+/// # Errors
+///
+/// Returns an error when the addresses of the interfaces can't be listed.
+pub fn addresses(interface: &str) -> io::Result<Addresses> {
+    let mut addresses = Addresses::default();
+    let mut link_local = None;
+
+    for address in getifaddrs()? {
+        if address.interface_name != interface {
+            continue;
+        }
+
+        let Some(address) = address.address else {
+            continue;
+        };
+
+        if let Some(ipv4) = address.as_sockaddr_in() {
+            addresses
+                .ipv4
+                .get_or_insert_with(|| Ipv4Addr::from(ipv4.ip()));
+        } else if let Some(ipv6) = address.as_sockaddr_in6() {
+            let ipv6 = ipv6.ip();
+
+            // Link-local addresses are in fe80::/10.
+            if ipv6.segments()[0] & 0xffc0 == 0xfe80 {
+                link_local.get_or_insert(ipv6);
+            } else {
+                addresses.ipv6.get_or_insert(ipv6);
+            }
+        }
+    }
+
+    addresses.ipv6 = addresses.ipv6.or(link_local);
+
+    Ok(addresses)
+}
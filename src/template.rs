@@ -0,0 +1,500 @@
+//! Templates for the text of blocks.
+//!
+//! A template is text containing variables in braces, for example `{icon} {capacity}%`. Each
+//! module documents the variables it provides.
+//!
+//! A variable can be padded to a minimum width by following its name with a format spec, similar
+//! to Rust's format strings:
+//!
+//! - `{capacity:3}` pads to 3 characters, aligning numbers to the right and text to the left.
+//! - `{capacity:<3}`, `{capacity:^3}` and `{capacity:>3}` align to the left, center or right.
+//! - `{capacity:03}` pads numbers with zeros.
+//! - `{load:.2}` shows 2 digits after the decimal point.
+//!
+//! Text in square brackets is only shown when every variable inside it has a value, for example
+//! `{icon}[ {battery}%]` only shows the battery percentage when it's known.
+//!
+//! The characters `{`, `}`, `[`, `]` and `\` can be escaped with a backslash.
+
+use serde::Deserialize;
+use std::{collections::HashMap, error::Error, fmt, str::FromStr};
+
+/// A template for the text of a block.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+impl Template {
+    /// Renders the template with the given variables.
+    ///
+    /// Variables without a value are rendered as empty text.
+    #[must_use]
+    pub fn render(&self, vars: &Vars) -> String {
+        let mut output = String::new();
+        render_segments(&self.segments, vars, &mut output);
+        output
+    }
+}
+
+impl FromStr for Template {
+    type Err = ParseError;
+
+    fn from_str(template: &str) -> Result<Self, Self::Err> {
+        // The segments of each unclosed optional section, starting with the top level.
+        let mut stack = vec![Vec::new()];
+        let mut text = String::new();
+        let mut chars = template.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => {
+                    text.push(chars.next().ok_or(ParseError::TrailingBackslash)?);
+                }
+                '{' => {
+                    let mut variable = String::new();
+
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => variable.push(c),
+                            None => return Err(ParseError::UnclosedVariable),
+                        }
+                    }
+
+                    push_text(&mut stack, &mut text);
+                    let segment = Segment::Variable(variable.parse()?);
+                    stack.last_mut().expect("top level").push(segment);
+                }
+                '[' => {
+                    push_text(&mut stack, &mut text);
+                    stack.push(Vec::new());
+                }
+                ']' => {
+                    push_text(&mut stack, &mut text);
+
+                    if stack.len() == 1 {
+                        return Err(ParseError::UnmatchedBracket);
+                    }
+
+                    let segments = stack.pop().expect("optional section");
+                    let segment = Segment::Optional(segments);
+                    stack.last_mut().expect("top level").push(segment);
+                }
+                '}' => return Err(ParseError::UnmatchedBrace),
+                c => text.push(c),
+            }
+        }
+
+        push_text(&mut stack, &mut text);
+
+        if stack.len() > 1 {
+            return Err(ParseError::UnclosedBracket);
+        }
+
+        Ok(Self {
+            segments: stack.pop().expect("top level"),
+        })
+    }
+}
+
+impl TryFrom<String> for Template {
+    type Error = ParseError;
+
+    fn try_from(template: String) -> Result<Self, Self::Error> {
+        template.parse()
+    }
+}
+
+/// An error parsing a [`Template`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// A variable is missing its closing `}`.
+    UnclosedVariable,
+    /// A `}` has no matching `{`.
+    UnmatchedBrace,
+    /// An optional section is missing its closing `]`.
+    UnclosedBracket,
+    /// A `]` has no matching `[`.
+    UnmatchedBracket,
+    /// The template ends with a `\` that doesn't escape anything.
+    TrailingBackslash,
+    /// A variable has no name.
+    EmptyVariable,
+    /// A variable has an invalid format spec.
+    InvalidSpec(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnclosedVariable => write!(f, "unclosed `{{`"),
+            Self::UnmatchedBrace => write!(f, "unmatched `}}`"),
+            Self::UnclosedBracket => write!(f, "unclosed `[`"),
+            Self::UnmatchedBracket => write!(f, "unmatched `]`"),
+            Self::TrailingBackslash => write!(f, "trailing `\\`"),
+            Self::EmptyVariable => write!(f, "variable without a name"),
+            Self::InvalidSpec(spec) => write!(f, "invalid format spec `{spec}`"),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// The value of a template variable.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// A text value.
+    Text(String),
+    /// An integer value.
+    Integer(i64),
+    /// A floating point value.
+    Float(f64),
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Self::Text(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Self::Text(value.to_owned())
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Self::Integer(value)
+    }
+}
+
+impl From<u64> for Value {
+    fn from(value: u64) -> Self {
+        Self::Integer(i64::try_from(value).unwrap_or(i64::MAX))
+    }
+}
+
+impl From<u8> for Value {
+    fn from(value: u8) -> Self {
+        Self::Integer(value.into())
+    }
+}
+
+impl From<u32> for Value {
+    fn from(value: u32) -> Self {
+        Self::Integer(value.into())
+    }
+}
+
+impl From<i16> for Value {
+    fn from(value: i16) -> Self {
+        Self::Integer(value.into())
+    }
+}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Self {
+        Self::Integer(value.into())
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Self::Float(value)
+    }
+}
+
+/// The variables available to a [`Template`].
+#[derive(Debug, Clone, Default)]
+pub struct Vars {
+    values: HashMap<&'static str, Value>,
+}
+
+impl Vars {
+    /// Creates an empty set of variables.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the value of a variable.
+    #[must_use]
+    pub fn with(mut self, name: &'static str, value: impl Into<Value>) -> Self {
+        self.values.insert(name, value.into());
+        self
+    }
+
+    /// Sets the value of a variable if there is one.
+    #[must_use]
+    pub fn with_option(self, name: &'static str, value: Option<impl Into<Value>>) -> Self {
+        match value {
+            Some(value) => self.with(name, value),
+            None => self,
+        }
+    }
+
+    /// Returns the value of a variable.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.values.get(name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Text(String),
+    Variable(Variable),
+    Optional(Vec<Self>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Variable {
+    name: String,
+    align: Option<Align>,
+    zero: bool,
+    width: usize,
+    precision: Option<usize>,
+}
+
+impl FromStr for Variable {
+    type Err = ParseError;
+
+    fn from_str(variable: &str) -> Result<Self, Self::Err> {
+        let (name, spec) = variable.split_once(':').unwrap_or((variable, ""));
+
+        if name.is_empty() {
+            return Err(ParseError::EmptyVariable);
+        }
+
+        let invalid_spec = || ParseError::InvalidSpec(spec.to_owned());
+
+        let (align, rest) = match spec.chars().next() {
+            Some('<') => (Some(Align::Left), &spec[1..]),
+            Some('^') => (Some(Align::Center), &spec[1..]),
+            Some('>') => (Some(Align::Right), &spec[1..]),
+            _ => (None, spec),
+        };
+
+        let (zero, rest) = rest
+            .strip_prefix('0')
+            .map_or((false, rest), |rest| (true, rest));
+
+        let (width, precision) = rest
+            .split_once('.')
+            .map_or((rest, None), |(width, precision)| (width, Some(precision)));
+
+        let width = if width.is_empty() {
+            0
+        } else {
+            width.parse().map_err(|_| invalid_spec())?
+        };
+
+        let precision = precision
+            .map(|precision| precision.parse().map_err(|_| invalid_spec()))
+            .transpose()?;
+
+        Ok(Self {
+            name: name.to_owned(),
+            align,
+            zero,
+            width,
+            precision,
+        })
+    }
+}
+
+impl Variable {
+    fn render(&self, value: &Value) -> String {
+        let (text, is_number) = match value {
+            Value::Text(text) => (text.clone(), false),
+            Value::Integer(integer) => (integer.to_string(), true),
+            Value::Float(float) => (
+                self.precision.map_or_else(
+                    || float.to_string(),
+                    |precision| format!("{float:.precision$}"),
+                ),
+                true,
+            ),
+        };
+
+        let padding = self.width.saturating_sub(text.chars().count());
+
+        if padding == 0 {
+            return text;
+        }
+
+        if self.zero && is_number {
+            let (sign, digits) = text
+                .strip_prefix('-')
+                .map_or(("", text.as_str()), |digits| ("-", digits));
+
+            return format!("{sign}{}{digits}", "0".repeat(padding));
+        }
+
+        let align = self
+            .align
+            .unwrap_or(if is_number { Align::Right } else { Align::Left });
+
+        let (left, right) = match align {
+            Align::Left => (0, padding),
+            Align::Center => (padding / 2, padding - padding / 2),
+            Align::Right => (padding, 0),
+        };
+
+        format!("{}{text}{}", " ".repeat(left), " ".repeat(right))
+    }
+}
+
+/// Moves any pending text into the innermost section.
+fn push_text(stack: &mut [Vec<Segment>], text: &mut String) {
+    if !text.is_empty() {
+        let segment = Segment::Text(std::mem::take(text));
+        stack.last_mut().expect("top level").push(segment);
+    }
+}
+
+/// Renders segments, returning whether every variable had a value.
+fn render_segments(segments: &[Segment], vars: &Vars, output: &mut String) -> bool {
+    let mut is_complete = true;
+
+    for segment in segments {
+        match segment {
+            Segment::Text(text) => output.push_str(text),
+            Segment::Variable(variable) => match vars.get(&variable.name) {
+                Some(value) => output.push_str(&variable.render(value)),
+                None => is_complete = false,
+            },
+            Segment::Optional(segments) => {
+                let mut optional = String::new();
+
+                if render_segments(segments, vars, &mut optional) {
+                    output.push_str(&optional);
+                }
+            }
+        }
+    }
+
+    is_complete
+}
+
+#[cfg(test)]
+// Templates look like format strings, but aren't passed to formatting macros.
+#[allow(clippy::literal_string_with_formatting_args)]
+mod tests {
+    use super::*;
+
+    fn render(template: &str, vars: &Vars) -> String {
+        template
+            .parse::<Template>()
+            .expect("valid template")
+            .render(vars)
+    }
+
+    #[test]
+    fn parse_errors() {
+        let cases = [
+            ("{icon", ParseError::UnclosedVariable),
+            ("icon}", ParseError::UnmatchedBrace),
+            ("[{icon}", ParseError::UnclosedBracket),
+            ("[[{icon}]", ParseError::UnclosedBracket),
+            ("{icon}]", ParseError::UnmatchedBracket),
+            ("{icon}\\", ParseError::TrailingBackslash),
+            ("{}", ParseError::EmptyVariable),
+            ("{:3}", ParseError::EmptyVariable),
+            ("{icon:x}", ParseError::InvalidSpec("x".into())),
+            ("{icon:3.x}", ParseError::InvalidSpec("3.x".into())),
+        ];
+
+        for (template, error) in cases {
+            assert_eq!(template.parse::<Template>(), Err(error), "{template}");
+        }
+    }
+
+    #[test]
+    fn escapes() {
+        let vars = Vars::new().with("icon", "x");
+
+        assert_eq!(render(r"\{icon\} \[{icon}\] \\", &vars), r"{icon} [x] \");
+    }
+
+    #[test]
+    fn missing_variables_are_empty() {
+        assert_eq!(render("a{missing}b", &Vars::new()), "ab");
+    }
+
+    #[test]
+    fn optional_sections() {
+        let template = "{icon}[ {battery}%]";
+
+        assert_eq!(render(template, &Vars::new().with("icon", "i")), "i");
+        assert_eq!(
+            render(
+                template,
+                &Vars::new().with("icon", "i").with("battery", 50_u8)
+            ),
+            "i 50%"
+        );
+    }
+
+    #[test]
+    fn nested_optional_sections() {
+        let template = "{a}[ {b}[ {c}] {d}]";
+        let vars = || Vars::new().with("a", "a").with("b", "b");
+
+        // A missing variable in an inner section only hides that section.
+        assert_eq!(render(template, &vars().with("d", "d")), "a b d");
+        assert_eq!(
+            render(template, &vars().with("c", "c").with("d", "d")),
+            "a b c d"
+        );
+
+        // A missing variable in an outer section hides it along with its inner sections.
+        assert_eq!(render(template, &vars().with("c", "c")), "a");
+    }
+
+    #[test]
+    fn alignment() {
+        let vars = Vars::new().with("text", "ab").with("number", 7_u8);
+
+        assert_eq!(render("|{text:5}|", &vars), "|ab   |");
+        assert_eq!(render("|{number:5}|", &vars), "|    7|");
+        assert_eq!(render("|{text:<5}|", &vars), "|ab   |");
+        assert_eq!(render("|{text:^5}|", &vars), "| ab  |");
+        assert_eq!(render("|{text:>5}|", &vars), "|   ab|");
+        assert_eq!(render("|{number:<5}|", &vars), "|7    |");
+        assert_eq!(render("|{text:1}|", &vars), "|ab|");
+    }
+
+    #[test]
+    fn zero_padding() {
+        let vars = Vars::new()
+            .with("positive", 7_u8)
+            .with("negative", -7_i32)
+            .with("f", -1.5)
+            .with("text", "ab");
+
+        assert_eq!(render("{positive:03}", &vars), "007");
+        assert_eq!(render("{negative:04}", &vars), "-007");
+        assert_eq!(render("{f:08.2}", &vars), "-0001.50");
+        assert_eq!(render("{text:04}", &vars), "ab  ");
+    }
+
+    #[test]
+    fn precision() {
+        let vars = Vars::new().with("f", 1.0 / 3.0).with("integer", 3_u8);
+
+        assert_eq!(render("{f:.2}", &vars), "0.33");
+        assert_eq!(render("{f:6.1}", &vars), "   0.3");
+        assert_eq!(render("{integer:.2}", &vars), "3");
+    }
+}
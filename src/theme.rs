@@ -0,0 +1,76 @@
+//! Named colors shared by every module.
+
+use crate::block::Block;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Named colors, which modules refer to instead of hard-coding colors.
+///
+/// A color is either a name from the theme, or a color like `#00ff00` which is used as is. The
+/// default theme has the following colors, which can be overridden or added to in the `[theme]`
+/// table of the config:
+///
+/// - `idle`: `#888888`
+/// - `info`: `#00aaff`
+/// - `good`: `#00ff00`
+/// - `warning`: `#ffff00`
+/// - `critical`: `#ff0000`
+/// - `error`: `#ff0000`, for errors.
+///
+/// Blocks without a color of their own are displayed with the color named after their
+/// [`State`](crate::block::State), for example `critical`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(from = "HashMap<String, String>")]
+pub struct Theme {
+    colors: HashMap<String, String>,
+}
+
+impl Theme {
+    /// Replaces the names of colors in a block with the colors from the theme, using the color of
+    /// its state when it doesn't have a color.
+    #[must_use]
+    pub fn apply(&self, mut block: Block) -> Block {
+        if block.color.is_none() {
+            block.color = block.state.map(|state| state.name().to_owned());
+        }
+
+        for color in [&mut block.color, &mut block.background, &mut block.border]
+            .into_iter()
+            .flatten()
+        {
+            if let Some(resolved) = self.colors.get(color.as_str()) {
+                color.clone_from(resolved);
+            }
+        }
+
+        block
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        let colors = [
+            ("idle", "#888888"),
+            ("info", "#00aaff"),
+            ("good", "#00ff00"),
+            ("warning", "#ffff00"),
+            ("critical", "#ff0000"),
+            ("error", "#ff0000"),
+        ];
+
+        Self {
+            colors: colors
+                .into_iter()
+                .map(|(name, color)| (name.into(), color.into()))
+                .collect(),
+        }
+    }
+}
+
+impl From<HashMap<String, String>> for Theme {
+    fn from(colors: HashMap<String, String>) -> Self {
+        let mut theme = Self::default();
+        theme.colors.extend(colors);
+        theme
+    }
+}
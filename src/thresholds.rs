@@ -0,0 +1,161 @@
+//! Thresholds for deciding the state of a block from a value.
+
+use crate::block::State;
+use serde::Deserialize;
+
+/// Thresholds for deciding the state of a block from a value, such as a battery capacity.
+///
+/// The `critical`, `warning` and `info` thresholds are checked in that order, and apply when the
+/// value is at the threshold or worse. The `good` threshold applies when no other threshold does
+/// and the value is at the threshold or better. Thresholds that aren't set are skipped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Thresholds {
+    /// The threshold for the good state.
+    pub good: Option<f64>,
+    /// The threshold for the info state.
+    pub info: Option<f64>,
+    /// The threshold for the warning state.
+    pub warning: Option<f64>,
+    /// The threshold for the critical state.
+    pub critical: Option<f64>,
+}
+
+impl Thresholds {
+    /// Returns the state of a value where lower values are worse, such as a battery capacity.
+    #[must_use]
+    pub fn state_of_low(&self, value: f64) -> Option<State> {
+        self.state(
+            |threshold| value <= threshold,
+            |threshold| value >= threshold,
+        )
+    }
+
+    /// Returns the state of a value where higher values are worse, such as CPU usage.
+    #[must_use]
+    pub fn state_of_high(&self, value: f64) -> Option<State> {
+        self.state(
+            |threshold| value >= threshold,
+            |threshold| value <= threshold,
+        )
+    }
+
+    fn state(
+        &self,
+        is_worse: impl Fn(f64) -> bool,
+        is_better: impl Fn(f64) -> bool,
+    ) -> Option<State> {
+        [
+            (self.critical, State::Critical),
+            (self.warning, State::Warning),
+            (self.info, State::Info),
+        ]
+        .into_iter()
+        .find_map(|(threshold, state)| {
+            threshold
+                .filter(|&threshold| is_worse(threshold))
+                .map(|_| state)
+        })
+        .or_else(|| {
+            self.good
+                .filter(|&threshold| is_better(threshold))
+                .map(|_| State::Good)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOW: Thresholds = Thresholds {
+        good: Some(80.0),
+        info: Some(50.0),
+        warning: Some(30.0),
+        critical: Some(15.0),
+    };
+
+    const HIGH: Thresholds = Thresholds {
+        good: Some(20.0),
+        info: Some(50.0),
+        warning: Some(70.0),
+        critical: Some(90.0),
+    };
+
+    #[test]
+    fn state_of_low() {
+        let cases = [
+            (0.0, Some(State::Critical)),
+            (15.0, Some(State::Critical)),
+            (15.5, Some(State::Warning)),
+            (30.0, Some(State::Warning)),
+            (30.5, Some(State::Info)),
+            (50.0, Some(State::Info)),
+            (50.5, None),
+            (79.5, None),
+            (80.0, Some(State::Good)),
+            (100.0, Some(State::Good)),
+        ];
+
+        for (value, state) in cases {
+            assert_eq!(LOW.state_of_low(value), state, "{value}");
+        }
+    }
+
+    #[test]
+    fn state_of_high() {
+        let cases = [
+            (100.0, Some(State::Critical)),
+            (90.0, Some(State::Critical)),
+            (89.5, Some(State::Warning)),
+            (70.0, Some(State::Warning)),
+            (69.5, Some(State::Info)),
+            (50.0, Some(State::Info)),
+            (49.5, None),
+            (20.5, None),
+            (20.0, Some(State::Good)),
+            (0.0, Some(State::Good)),
+        ];
+
+        for (value, state) in cases {
+            assert_eq!(HIGH.state_of_high(value), state, "{value}");
+        }
+    }
+
+    #[test]
+    fn worse_thresholds_are_checked_first() {
+        // Overlapping thresholds apply in the order critical, warning, info, then good.
+        let thresholds = Thresholds {
+            good: Some(50.0),
+            info: Some(50.0),
+            warning: Some(50.0),
+            critical: Some(50.0),
+        };
+
+        assert_eq!(thresholds.state_of_low(50.0), Some(State::Critical));
+        assert_eq!(thresholds.state_of_high(50.0), Some(State::Critical));
+        assert_eq!(thresholds.state_of_low(60.0), Some(State::Good));
+        assert_eq!(thresholds.state_of_high(40.0), Some(State::Good));
+    }
+
+    #[test]
+    fn only_good() {
+        let thresholds = Thresholds {
+            good: Some(50.0),
+            ..Thresholds::default()
+        };
+
+        assert_eq!(thresholds.state_of_low(49.0), None);
+        assert_eq!(thresholds.state_of_low(50.0), Some(State::Good));
+        assert_eq!(thresholds.state_of_low(51.0), Some(State::Good));
+        assert_eq!(thresholds.state_of_high(49.0), Some(State::Good));
+        assert_eq!(thresholds.state_of_high(50.0), Some(State::Good));
+        assert_eq!(thresholds.state_of_high(51.0), None);
+    }
+
+    #[test]
+    fn no_thresholds() {
+        assert_eq!(Thresholds::default().state_of_low(0.0), None);
+        assert_eq!(Thresholds::default().state_of_high(100.0), None);
+    }
+}
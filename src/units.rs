@@ -0,0 +1,30 @@
+//! Formatting of quantities with units.
+
+/// Converts a quantity to a float, losing precision above 2^53, which is far beyond any quantity
+/// shown in a bar.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub const fn as_f64(value: u64) -> f64 {
+    value as f64
+}
+
+/// Formats a number of bytes with the largest binary unit that keeps it at least 1, like
+/// `1.5 GiB`.
+#[must_use]
+pub fn bytes(bytes: f64) -> String {
+    const UNITS: [&str; 6] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
+
+    let mut bytes = bytes;
+    let mut unit = 0;
+
+    while bytes >= 1024.0 && unit < UNITS.len() - 1 {
+        bytes /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{bytes:.0} {}", UNITS[unit])
+    } else {
+        format!("{bytes:.1} {}", UNITS[unit])
+    }
+}